@@ -18,77 +18,274 @@ limitations under the License.
 // * https://docs.atlassian.com/software/jira/docs/api/REST/latest/
 // * https://docs.atlassian.com/jira-software/REST/latest/
 
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::OnceCell;
+use url::form_urlencoded;
+
+use crate::adf::RichText;
+use crate::agile::{Board, BoardConfiguration, Epic};
+use crate::edit::{self, CommentBody, CreatedIssue, IssueEdit};
 use crate::errors::JiraQueryError;
-use crate::issue_model::{Issue, JqlResults};
+use crate::fields::{FieldDefinition, FieldRegistry};
+use crate::graph::{self, Edge, GraphOptions, IssueGraph, Relation};
+use crate::issue_model::{
+    Attachment, BulkFetchResults, ChangeHistory, Comment, Issue, IssueLinkType,
+    IssueRepresentation, JqlResults, PageBean, PartialIssue, Sprint, SprintState, Visibility,
+};
+use crate::jql::{self, Jql};
+use crate::links::{CreatedRemoteLink, IssueLinkTypes, RemoteLink, RemoteLinkEdit};
+use crate::options::{Expand, RequestOptions};
+use crate::retry::RetryPolicy;
+use crate::workflow::{Transition, TransitionBody, TransitionId, TransitionRequest, Transitions};
+use crate::worklog::{
+    self, EstimateAdjustment, UpdatedWorklogs, Worklog, WorklogChanges, WorklogEntry, WorklogIds,
+};
+
+// The options of requests that don't specify any, which outlive every stream.
+static NO_OPTIONS: RequestOptions = RequestOptions::new();
+
+// The longest URL that the GET search uses. Longer queries switch to the POST search.
+// Common servers and proxies accept at least 4 KiB, so this leaves enough room for headers.
+const MAX_URL_LENGTH: usize = 2000;
 
-// The prefix of every subsequent REST request.
-// This string comes directly after the host in the URL.
-const REST_PREFIX: &str = "rest/api/2";
+// The largest number of keys in a single JQL query. Longer lists of keys split
+// into several queries, because Jira limits the complexity of a query.
+const MAX_KEYS_PER_QUERY: usize = 250;
+
+// The largest number of keys that the Jira Cloud bulk fetch accepts in a single request.
+const MAX_KEYS_PER_BULK_FETCH: usize = 100;
+
+// The largest number of worklogs that Jira returns for a single request by their IDs.
+const MAX_WORKLOGS_PER_LIST: usize = 1000;
+
+// The path to the Jira Software (Agile) REST API, relative to the host.
+const AGILE_PREFIX: &str = "rest/agile/1.0";
 
 /// Configuration and credentials to access a Jira instance.
 pub struct JiraInstance {
     pub host: String,
     pub auth: Auth,
     pub pagination: Pagination,
+    pub retry: RetryPolicy,
+    pub deployment: Deployment,
+    pub api_version: ApiVersion,
     client: reqwest::Client,
+    // The fields of the instance, downloaded on first use.
+    field_registry: OnceCell<FieldRegistry>,
+}
+
+/// The kind of Jira deployment, which determines the REST endpoints that the instance provides:
+///
+/// * `Server`: Jira Server or Data Center. Searches paginate by the position of the first issue.
+/// * `Cloud`: Jira Cloud. Searches use the enhanced search endpoint, which paginates by a token,
+///   and requests for several keys use the bulk fetch endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Deployment {
+    #[default]
+    Server,
+    Cloud,
+}
+
+/// The version of the Jira REST API that the instance uses:
+///
+/// * `V2`: Rich text fields, such as the description, contain the Jira wiki markup.
+/// * `V3`: Rich text fields contain the Atlassian Document Format. Only Jira Cloud provides v3.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ApiVersion {
+    #[default]
+    V2,
+    V3,
+}
+
+impl ApiVersion {
+    /// The path to the REST API of this version, relative to the host.
+    const fn rest_prefix(self) -> &'static str {
+        match self {
+            Self::V2 => "rest/api/2",
+            Self::V3 => "rest/api/3",
+        }
+    }
 }
 
 /// The authentication method used to contact Jira.
+#[derive(Default)]
 pub enum Auth {
+    #[default]
     Anonymous,
     ApiKey(String),
-    Basic { user: String, password: String },
-}
-
-// We could set a default enum variant and derive, but that raises the MSRV to 1.62.
-impl Default for Auth {
-    fn default() -> Self {
-        Self::Anonymous
-    }
+    Basic {
+        user: String,
+        password: String,
+    },
 }
 
 /// Controls the upper limit of how many tickets the response from Jira can contain:
 ///
 /// * `Default`: Use the default settings of this instance, which sets an arbitrary limit on the number of tickets.
 /// * `MaxResults`: Set the upper limit to this value. Note that each instance has a maximum allowed value,
-/// and if you set `MaxResults` higher than that, the instance uses its own maximum allowed value.
+///   and if you set `MaxResults` higher than that, the instance uses its own maximum allowed value.
 /// * `ChunkSize`: Access the tickets in a series of requests, each accessing the number of tickets equal to the chunk size.
-/// This enables you to access an unlimited number of tickets, as long as the chunk size is smaller
-/// than the maximum allowed results size for the instance.
+///   This enables you to access an unlimited number of tickets, as long as the chunk size is smaller
+///   than the maximum allowed results size for the instance.
+/// * `Parallel`: Access the tickets in a series of chunks like `ChunkSize`, but once the first chunk
+///   reveals the total number of tickets, request the remaining chunks concurrently,
+///   with at most `max_in_flight` requests at the same time. The tickets keep their order.
+///   On Jira Cloud, each page requires the token from the previous one, so the chunks
+///   download one after another like `ChunkSize`.
+#[derive(Default)]
 pub enum Pagination {
+    #[default]
     Default,
     MaxResults(u32),
     ChunkSize(u32),
+    Parallel {
+        chunk_size: u32,
+        max_in_flight: usize,
+    },
+}
+
+/// The issues that Jira returned in response to a request for several keys.
+#[derive(Clone, Debug)]
+pub struct FoundIssues<T = Issue> {
+    /// The issues that Jira returned.
+    pub issues: Vec<T>,
+    /// The requested keys that Jira returned no issue for.
+    pub missing: Vec<String>,
+    /// The requested keys of issues that moved, paired with the current key of each issue.
+    pub moved: Vec<(String, String)>,
 }
 
-// We could set a default enum variant and derive, but that raises the MSRV to 1.62.
-impl Default for Pagination {
+// Deriving `Default` would require `T: Default`, which issues don't implement.
+impl<T> Default for FoundIssues<T> {
     fn default() -> Self {
-        Self::Default
+        Self {
+            issues: Vec::new(),
+            missing: Vec::new(),
+            moved: Vec::new(),
+        }
     }
 }
 
 /// The method of the request to Jira. Either request specific IDs,
 /// or use a free-form JQL search query.
+#[derive(Clone, Copy)]
 enum Method<'a> {
     Key(&'a str),
     Keys(&'a [&'a str]),
     Search(&'a str),
+    /// An endpoint of the Agile API that lists issues, such as `sprint/1/issue`.
+    Agile(&'a str),
 }
 
-impl<'a> Method<'a> {
-    fn url_fragment(&self) -> String {
+impl Method<'_> {
+    /// The path of the REST endpoint, which comes after the REST prefix in the URL.
+    const fn url_fragment(&self) -> &str {
+        match self {
+            Self::Key(_) => "issue",
+            Self::Keys(_) | Self::Search(_) => "search",
+            Self::Agile(endpoint) => endpoint,
+        }
+    }
+
+    /// The JQL query that selects the requested issues, if the method uses a search.
+    fn jql(&self) -> Option<String> {
+        match self {
+            Self::Key(_) | Self::Agile(_) => None,
+            Self::Keys(ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| jql::quote(id)).collect();
+                Some(format!("id in ({})", ids.join(",")))
+            }
+            Self::Search(query) => Some((*query).to_string()),
+        }
+    }
+
+    /// Whether Jira should reject a query that refers to values that don't exist.
+    /// Without validation, Jira skips keys that don't exist rather than reject the query.
+    const fn validate_query(&self) -> Option<bool> {
         match self {
-            Self::Key(id) => format!("issue/{id}"),
-            Self::Keys(ids) => format!("search?jql=id%20in%20({})", ids.join(",")),
-            Self::Search(query) => format!("search?jql={query}"),
+            Self::Keys(_) => Some(false),
+            Self::Key(_) | Self::Search(_) | Self::Agile(_) => None,
+        }
+    }
+}
+
+/// Percent-encode a value, such as an issue key, for use as a single segment of a URL path.
+/// Only the unreserved characters stay as they are.
+fn segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            // Writing to a string can't fail.
+            let _ = write!(encoded, "%{byte:02X}");
         }
     }
+    encoded
+}
+
+/// The body of a JQL search that uses the POST method,
+/// which isn't limited by the maximum length of a URL.
+#[derive(Serialize)]
+struct SearchBody<'a> {
+    jql: String,
+    #[serde(rename = "startAt")]
+    start_at: u32,
+    #[serde(rename = "maxResults", skip_serializing_if = "Option::is_none")]
+    max_results: Option<u32>,
+    #[serde(rename = "validateQuery", skip_serializing_if = "Option::is_none")]
+    validate_query: Option<bool>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    fields: &'a [String],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    expand: Vec<&'static str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    properties: &'a [String],
+}
+
+/// The body of a search on the Jira Cloud enhanced search endpoint.
+#[derive(Serialize)]
+struct EnhancedSearchBody<'a> {
+    jql: String,
+    #[serde(rename = "nextPageToken", skip_serializing_if = "Option::is_none")]
+    next_page_token: Option<&'a str>,
+    #[serde(rename = "maxResults", skip_serializing_if = "Option::is_none")]
+    max_results: Option<u32>,
+    fields: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expand: Option<String>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    properties: &'a [String],
+}
+
+/// The body of a request to the Jira Cloud bulk fetch endpoint.
+#[derive(Serialize)]
+struct BulkFetchBody<'a> {
+    #[serde(rename = "issueIdsOrKeys")]
+    issue_ids_or_keys: &'a [&'a str],
+    fields: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    expand: Vec<&'static str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    properties: &'a [String],
 }
 
 impl JiraInstance {
     /// Create a new `BzInstance` struct using a host URL, with default values
     /// for all options.
+    ///
+    /// # Errors
+    ///
+    /// Currently never fails. The `Result` leaves room for validating the host URL.
     pub fn at(host: String) -> Result<Self, JiraQueryError> {
         // TODO: This function takes host as a String, even though client is happy with &str.
         // The String is only used in the host struct attribute.
@@ -99,6 +296,10 @@ impl JiraInstance {
             client,
             auth: Auth::default(),
             pagination: Pagination::default(),
+            retry: RetryPolicy::default(),
+            deployment: Deployment::default(),
+            api_version: ApiVersion::default(),
+            field_registry: OnceCell::new(),
         })
     }
 
@@ -123,56 +324,313 @@ impl JiraInstance {
         self
     }
 
+    /// Set the kind of Jira deployment that this `JiraInstance` connects to.
+    #[must_use]
+    pub const fn deployment(mut self, deployment: Deployment) -> Self {
+        self.deployment = deployment;
+        self
+    }
+
+    /// Set the version of the REST API that this `JiraInstance` uses.
+    #[must_use]
+    pub const fn api_version(mut self, api_version: ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Set the retry policy of this `JiraInstance`.
+    #[must_use]
+    pub const fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
     /// Based on the request method, form a complete, absolute URL
     /// to download the tickets from the REST API.
     #[must_use]
-    fn path(&self, method: &Method, start_at: u32) -> String {
-        let max_results = match self.pagination {
-            Pagination::Default => String::new(),
-            // For both MaxResults and ChunkSIze, set the maxResults size to the value set in the variant.
-            // The maxResults size is relevant for ChunkSize in that each chunk requires its own results
-            // to be at least this large.
-            Pagination::MaxResults(n) | Pagination::ChunkSize(n) => format!("&maxResults={n}"),
-        };
+    fn path(&self, method: &Method, options: &RequestOptions, start_at: u32) -> String {
+        let mut params = Vec::new();
+
+        if let Some(jql) = method.jql() {
+            params.push(("jql", jql));
+        }
+        if let Some(validate) = method.validate_query() {
+            params.push(("validateQuery", validate.to_string()));
+        }
+
+        // The pagination options are only valid with JQL. With a URL by key, they break the REST query.
+        if let Method::Keys(_) | Method::Search(_) | Method::Agile(_) = method {
+            if let Some(max_results) = self.max_results() {
+                params.push(("maxResults", max_results.to_string()));
+            }
+            params.push(("startAt", start_at.to_string()));
+        }
+
+        params.extend(options.query_params());
 
-        // The `startAt` option is only valid with JQL. With a URL by key, it breaks the REST query.
-        let start_at = match method {
-            Method::Key(_) => String::new(),
-            Method::Keys(_) | Method::Search(_) => format!("&startAt={start_at}"),
+        let endpoint = match method {
+            Method::Key(key) => format!("{}/{}", method.url_fragment(), segment(key)),
+            Method::Keys(_) | Method::Search(_) | Method::Agile(_) => {
+                method.url_fragment().to_string()
+            }
         };
 
-        format!(
-            "{}/{}/{}{}{}",
-            self.host,
-            REST_PREFIX,
-            method.url_fragment(),
-            max_results,
+        if let Method::Agile(_) = method {
+            self.agile_url(&endpoint, &params)
+        } else {
+            self.rest_url(&endpoint, &params)
+        }
+    }
+
+    /// The same search as `path`, but in the form of a body for the POST search.
+    /// The JQL query must come from the method, so that the search keeps the scope of the request.
+    fn search_body<'a>(
+        &self,
+        jql: String,
+        method: &Method,
+        options: &'a RequestOptions,
+        start_at: u32,
+    ) -> SearchBody<'a> {
+        SearchBody {
+            jql,
             start_at,
-        )
+            max_results: self.max_results(),
+            validate_query: method.validate_query(),
+            fields: &options.fields,
+            expand: options
+                .expand
+                .iter()
+                .map(|expand| expand.as_str())
+                .collect(),
+            properties: &options.properties,
+        }
     }
 
-    /// Download the specified URL using the configured authentication.
-    async fn authenticated_get(&self, url: &str) -> Result<reqwest::Response, reqwest::Error> {
-        let request_builder = self.client.get(url);
-        let authenticated = match &self.auth {
+    /// The page size that the configured pagination requests.
+    const fn max_results(&self) -> Option<u32> {
+        match self.pagination {
+            Pagination::Default => None,
+            // For MaxResults, ChunkSize, and Parallel, set the maxResults size to the value set in the variant.
+            // The maxResults size is relevant for ChunkSize in that each chunk requires its own results
+            // to be at least this large.
+            Pagination::MaxResults(n)
+            | Pagination::ChunkSize(n)
+            | Pagination::Parallel { chunk_size: n, .. } => Some(n),
+        }
+    }
+
+    /// Form a complete, absolute URL to a REST endpoint, such as `issue/KEY-1/changelog`,
+    /// with the specified query parameters. The parameter values are URL-encoded.
+    fn rest_url(&self, endpoint: &str, params: &[(&str, String)]) -> String {
+        self.api_url(self.api_version.rest_prefix(), endpoint, params)
+    }
+
+    /// Form a complete, absolute URL to an endpoint of the Agile API, such as `board/1/sprint`,
+    /// with the specified query parameters.
+    fn agile_url(&self, endpoint: &str, params: &[(&str, String)]) -> String {
+        self.api_url(AGILE_PREFIX, endpoint, params)
+    }
+
+    /// Form a complete, absolute URL to an endpoint of the API under the prefix.
+    fn api_url(&self, prefix: &str, endpoint: &str, params: &[(&str, String)]) -> String {
+        let mut url = format!("{}/{prefix}/{endpoint}", self.host);
+
+        if !params.is_empty() {
+            let query = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params)
+                .finish();
+            url.push('?');
+            url.push_str(&query);
+        }
+
+        url
+    }
+
+    /// Download all items of a list that Jira splits into pages with the `startAt` parameter,
+    /// such as the changelog of an issue. The page size follows the configured pagination,
+    /// but the list always downloads completely.
+    ///
+    /// The `prefix` selects the API, such as the Agile API, that provides the endpoint.
+    async fn paged_values<T: DeserializeOwned>(
+        &self,
+        prefix: &str,
+        endpoint: &str,
+        params: &[(&str, String)],
+    ) -> Result<Vec<T>, JiraQueryError> {
+        let mut values = Vec::new();
+        let mut start_at = 0;
+
+        loop {
+            let mut page_params = params.to_vec();
+            page_params.push(("startAt", start_at.to_string()));
+            if let Some(max_results) = self.max_results() {
+                page_params.push(("maxResults", max_results.to_string()));
+            }
+
+            let url = self.api_url(prefix, endpoint, &page_params);
+            let mut page: PageBean<T> = self.get_json(&url).await?;
+            let is_last_page = page.is_last_page();
+
+            start_at = page.start_at + u32::try_from(page.values.len()).unwrap_or(u32::MAX);
+            values.append(&mut page.values);
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Add the configured authentication to the request.
+    fn authorize(&self, request_builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth {
             Auth::Anonymous => request_builder,
             Auth::ApiKey(key) => request_builder.header("Authorization", &format!("Bearer {key}")),
             Auth::Basic { user, password } => request_builder.basic_auth(user, Some(password)),
-        };
-        authenticated.send().await
+        }
+    }
+
+    /// Send the request using the configured authentication.
+    ///
+    /// If a GET request fails for a transient reason, retry it according to the retry policy.
+    /// Other requests only get one attempt. Send requests that modify data with `send_write`.
+    async fn send(
+        &self,
+        request_builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let request = self.authorize(request_builder).build()?;
+        if request.method() != reqwest::Method::GET {
+            return self.client.execute(request).await;
+        }
+        let mut attempt = 1;
+
+        loop {
+            // A request with a streaming body can't be repeated, so it only gets one attempt.
+            let Some(current) = request.try_clone() else {
+                return self.client.execute(request).await;
+            };
+            let result = self.client.execute(current).await;
+
+            match self.retry.next_delay(attempt, &result) {
+                Some(delay) => {
+                    log::warn!(
+                        "Request to {} failed on attempt {attempt}. Retrying in {delay:?}.",
+                        request.url()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return result,
+            }
+        }
+    }
+
+    /// Download the specified URL using the configured authentication.
+    async fn authenticated_get(&self, url: &str) -> Result<reqwest::Response, reqwest::Error> {
+        self.send(self.client.get(url)).await
+    }
+
+    /// Download the specified URL and deserialize the JSON response.
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, JiraQueryError> {
+        let response = self.authenticated_get(url).await?;
+
+        Self::parse_response(url, response).await
+    }
+
+    /// Send the body as JSON to the specified URL and deserialize the JSON response.
+    /// Only use this function for requests that only read data, such as a search.
+    /// The POST request isn't idempotent, so it's never retried.
+    async fn post_json<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        body: &B,
+    ) -> Result<T, JiraQueryError> {
+        let response = self.send(self.client.post(url).json(body)).await?;
+
+        Self::parse_response(url, response).await
+    }
+
+    /// Send a request that modifies data, and return the body of the response.
+    ///
+    /// Unlike `send`, this function never retries the request,
+    /// because repeating it might apply the change twice.
+    async fn send_write(
+        &self,
+        request_builder: reqwest::RequestBuilder,
+    ) -> Result<String, JiraQueryError> {
+        let response = self.authorize(request_builder).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(JiraQueryError::from_response(status, &body));
+        }
+
+        Ok(body)
+    }
+
+    /// Send a request that modifies data, and deserialize the JSON response.
+    async fn write_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        request_builder: reqwest::RequestBuilder,
+    ) -> Result<T, JiraQueryError> {
+        let body = self.send_write(request_builder).await?;
+
+        serde_json::from_str(&body).map_err(|e| JiraQueryError::deserialize(url, &body, e))
+    }
+
+    /// Deserialize the JSON body of the response.
+    ///
+    /// If Jira responds with an error status, report the error messages that Jira
+    /// attaches to the response instead of attempting to deserialize the body.
+    async fn parse_response<T: DeserializeOwned>(
+        url: &str,
+        response: reqwest::Response,
+    ) -> Result<T, JiraQueryError> {
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(JiraQueryError::from_response(status, &body));
+        }
+
+        serde_json::from_str(&body).map_err(|e| JiraQueryError::deserialize(url, &body, e))
     }
 
     // This method uses a separate implementation from `issues` because Jira provides a way
     // to request a single ticket specifically. That conveniently handles error cases
     // where no tickets might match, or more than one might.
     /// Access a single issue by its key.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it,
+    /// and with another variant if the request fails or the response doesn't match `Issue`.
     pub async fn issue(&self, key: &str) -> Result<Issue, JiraQueryError> {
-        let url = self.path(&Method::Key(key), 0);
+        self.issue_with(key, &RequestOptions::new()).await
+    }
+
+    /// Access a single issue by its key, with options that control the content of the issue.
+    ///
+    /// If the options reduce the set of fields, deserialize the issue as `PartialIssue`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it,
+    /// and with `JiraQueryError::Deserialize` if the response lacks a field that `T` requires.
+    pub async fn issue_with<T: IssueRepresentation>(
+        &self,
+        key: &str,
+        options: &RequestOptions,
+    ) -> Result<T, JiraQueryError> {
+        let url = self.path(&Method::Key(key), options, 0);
 
         // Gets an issue by ID and deserializes the JSON to data variable
-        let issue = self.authenticated_get(&url).await?.json::<Issue>().await?;
+        let issue: T = self.get_json(&url).await?;
 
-        log::debug!("{:#?}", issue);
+        log::debug!("{issue:#?}");
 
         Ok(issue)
     }
@@ -180,104 +638,1230 @@ impl JiraInstance {
     /// Access several issues by their keys.
     ///
     /// If the list of keys is empty, returns an empty list back with no errors.
+    ///
+    /// # Errors
+    ///
+    /// If Jira doesn't return some of the requested issues, because they don't exist
+    /// or because the account can't see them, fails with `JiraQueryError::MissingIssues`.
+    /// To receive the issues that Jira did return, use `issues_allow_missing` instead.
     pub async fn issues(&self, keys: &[&str]) -> Result<Vec<Issue>, JiraQueryError> {
+        self.issues_with(keys, &RequestOptions::new()).await
+    }
+
+    /// Access several issues by their keys, with options that control the content of the issues.
+    ///
+    /// Handles missing issues the same way as `issues`.
+    /// If the options reduce the set of fields, deserialize the issues as `PartialIssue`.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same cases as `issues`, and with `JiraQueryError::Deserialize`
+    /// if the response lacks a field that `T` requires.
+    pub async fn issues_with<T: IssueRepresentation>(
+        &self,
+        keys: &[&str],
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        let found = self.find_issues(keys, options).await?;
+
+        if found.missing.is_empty() {
+            Ok(found.issues)
+        } else {
+            Err(JiraQueryError::MissingIssues(found.missing))
+        }
+    }
+
+    /// Access several issues by their keys, and tolerate keys that Jira doesn't return.
+    ///
+    /// The result lists the found issues, the requested keys that Jira didn't return,
+    /// and the requested keys that now belong to an issue under a different key.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a request fails or Jira responds with an error. Keys that Jira doesn't return
+    /// aren't an error.
+    pub async fn issues_allow_missing(&self, keys: &[&str]) -> Result<FoundIssues, JiraQueryError> {
+        self.find_issues(keys, &RequestOptions::new()).await
+    }
+
+    /// Download the issues by their keys and sort the keys into found, missing, and moved.
+    async fn find_issues<T: IssueRepresentation>(
+        &self,
+        keys: &[&str],
+        options: &RequestOptions,
+    ) -> Result<FoundIssues<T>, JiraQueryError> {
         // If the user specifies no keys, skip network requests and return no bugs.
         // Returning an error could also be valid, but I believe that this behavior
         // is less surprising and more practical.
         if keys.is_empty() {
-            return Ok(Vec::new());
+            return Ok(FoundIssues::default());
         }
 
-        let method = Method::Keys(keys);
+        let mut issues = Vec::new();
 
-        // If Pagination is set to ChunkSize, split the issue keys into chunk by chunk size
-        // and request each chunk separately.
-        if let Pagination::ChunkSize(chunk_size) = self.pagination {
-            self.paginated_issues(&method, chunk_size).await
-        // If Pagination is not set to ChunkSize, use a single chunk request for all issues.
-        } else {
-            let issues = self.chunk_of_issues(&method, 0).await?;
+        // Split a long list of keys into several queries.
+        for keys_chunk in keys.chunks(self.keys_per_query()) {
+            let method = Method::Keys(keys_chunk);
+            let mut chunk_issues = self.all_issues(&method, options).await?;
+            issues.append(&mut chunk_issues);
+        }
 
-            // If the resulting list is empty, return an error.
-            // TODO: The REST parsing above already results in an error if the results are empty.
-            // Try to catch the error there.
-            if issues.is_empty() {
-                Err(JiraQueryError::NoIssues)
-            } else {
-                Ok(issues)
+        self.match_requested_keys(keys, options, issues).await
+    }
+
+    /// Compare the requested keys with the keys of the issues that Jira returned.
+    ///
+    /// When an issue moves to another project, Jira still finds it by its original key,
+    /// but returns it under the new key. If some returned issues don't match any requested key,
+    /// request each unmatched key separately to tell moved issues from missing ones.
+    async fn match_requested_keys<T: IssueRepresentation>(
+        &self,
+        keys: &[&str],
+        options: &RequestOptions,
+        mut issues: Vec<T>,
+    ) -> Result<FoundIssues<T>, JiraQueryError> {
+        // Jira keys are case-insensitive.
+        let requested: HashSet<String> = keys.iter().map(|key| key.to_uppercase()).collect();
+        let returned: HashSet<String> = issues
+            .iter()
+            .map(|issue| issue.key().to_uppercase())
+            .collect();
+
+        let unmatched_keys: Vec<&str> = keys
+            .iter()
+            .copied()
+            .filter(|key| !returned.contains(&key.to_uppercase()))
+            .collect();
+        let has_unmatched_issues = returned.iter().any(|key| !requested.contains(key));
+
+        let mut missing = Vec::new();
+        let mut moved = Vec::new();
+
+        if has_unmatched_issues {
+            for key in unmatched_keys {
+                match self.issue_with::<T>(key, options).await {
+                    Ok(issue) => {
+                        moved.push((key.to_string(), issue.key().to_string()));
+                        if !returned.contains(&issue.key().to_uppercase()) {
+                            issues.push(issue);
+                        }
+                    }
+                    Err(JiraQueryError::NotFound(_)) => missing.push(key.to_string()),
+                    Err(error) => return Err(error),
+                }
             }
+        } else {
+            // Every returned issue matches a requested key, so no issue could have moved.
+            missing = unmatched_keys.into_iter().map(String::from).collect();
         }
+
+        Ok(FoundIssues {
+            issues,
+            missing,
+            moved,
+        })
     }
 
-    /// Download all issues specified in the request as a series of chunks or pages.
-    /// The request controls whether the download works with IDs or JQL.
-    /// This function only processes the resulting pages coming back from Jira
-    /// and stops the iteration at the last page.
+    /// Download all issues specified in the request. The request controls whether
+    /// the download works with IDs or JQL.
     ///
-    /// See the Jira documentation:
-    /// <https://confluence.atlassian.com/jirakb/changing-maxresults-parameter-for-jira-rest-api-779160706.html>.
-    async fn paginated_issues(
+    /// If `Pagination` is set to `ChunkSize` or `Parallel`, download the issues
+    /// as a series of chunks or pages. Otherwise, use a single chunk request for all issues.
+    async fn all_issues<T: IssueRepresentation>(
         &self,
         method: &Method<'_>,
-        chunk_size: u32,
-    ) -> Result<Vec<Issue>, JiraQueryError> {
-        let mut all_issues = Vec::new();
-        let mut start_at = 0;
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        self.pages(*method, options, self.paginates(method))
+            .map_ok(|page| page.issues)
+            .try_concat()
+            .await
+    }
 
-        loop {
-            let mut chunk_issues = self.chunk_of_issues(method, start_at).await?;
-            // Calculate the length now before the content moves to `all_issues`.
-            let page_size = chunk_issues.len();
-            all_issues.append(&mut chunk_issues);
-
-            // If this page contains fewer issues than the chunk size,
-            // it's the last page. Stop the loop.
-            if page_size < chunk_size as usize {
-                break;
+    /// Lazily download the issues specified in the request as a series of chunks or pages.
+    /// Each item of the stream is a single page. The next page is only requested
+    /// when the consumer polls the stream after receiving the previous page.
+    ///
+    /// This function follows the `startAt` and `total` values in the pages
+    /// coming back from Jira and stops the iteration at the last page.
+    /// Each page continues where the previous one ended, even if the instance
+    /// caps the page size below the requested chunk size.
+    /// If `paginate` is false, the stream contains only the first page.
+    ///
+    /// See the Jira documentation:
+    /// <https://confluence.atlassian.com/jirakb/changing-maxresults-parameter-for-jira-rest-api-779160706.html>.
+    fn pages<'a, T: IssueRepresentation>(
+        &'a self,
+        method: Method<'a>,
+        options: &'a RequestOptions,
+        paginate: bool,
+    ) -> BoxStream<'a, Result<JqlResults<T>, JiraQueryError>> {
+        match (self.deployment, method) {
+            (Deployment::Cloud, Method::Keys(keys)) => {
+                return stream::once(async move { self.bulk_fetch(keys, options).await }).boxed();
+            }
+            // The Agile API paginates by position even on Jira Cloud.
+            (Deployment::Cloud, Method::Key(_) | Method::Search(_)) => {
+                return self.token_pages(method, options, paginate);
             }
+            (Deployment::Cloud, Method::Agile(_)) | (Deployment::Server, _) => {}
+        }
 
-            start_at += chunk_size;
+        if let (true, Pagination::Parallel { max_in_flight, .. }) = (paginate, &self.pagination) {
+            return self.parallel_pages(method, options, *max_in_flight);
         }
 
-        Ok(all_issues)
+        // The state is the position of the next page, or `None` after the last page.
+        stream::try_unfold(Some(0), move |start_at| async move {
+            let Some(start_at) = start_at else {
+                return Ok(None);
+            };
+
+            let page = self.chunk_of_issues(&method, options, start_at).await?;
+
+            let next_start_at = if paginate && !page.is_last_page() {
+                Some(page.fetched())
+            } else {
+                None
+            };
+
+            Ok(Some((page, next_start_at)))
+        })
+        .boxed()
+    }
+
+    /// Download the first page of the request, and then the remaining pages concurrently.
+    ///
+    /// The first page reveals the total number of issues and the page size that the instance
+    /// applies, which determine the positions of the remaining pages. The stream keeps
+    /// the pages in order. If any page fails, the stream ends with the error
+    /// and the remaining requests are cancelled once the consumer drops the stream.
+    fn parallel_pages<'a, T: IssueRepresentation>(
+        &'a self,
+        method: Method<'a>,
+        options: &'a RequestOptions,
+        max_in_flight: usize,
+    ) -> BoxStream<'a, Result<JqlResults<T>, JiraQueryError>> {
+        stream::once(async move { self.chunk_of_issues(&method, options, 0).await })
+            .map_ok(move |first| {
+                let remaining = if first.is_last_page() {
+                    Vec::new()
+                } else {
+                    // The instance might cap the page size below the requested chunk size,
+                    // so step by the actual size of the first page.
+                    let page_size = first.fetched();
+                    (page_size..first.total.unwrap_or_default())
+                        .step_by(page_size as usize)
+                        .collect()
+                };
+
+                let rest = stream::iter(remaining)
+                    .map(move |start_at| async move {
+                        self.chunk_of_issues(&method, options, start_at).await
+                    })
+                    // A zero limit would never start any request.
+                    .buffered(max_in_flight.max(1));
+
+                stream::once(future::ready(Ok(first))).chain(rest)
+            })
+            .try_flatten()
+            .boxed()
+    }
+
+    /// Lazily download the issues specified in the request from the Jira Cloud enhanced search,
+    /// which links each page to the next one with a token.
+    ///
+    /// Jira Cloud doesn't report the position of the page, so the stream counts it
+    /// and fills in `start_at` so that `fetched` works the same as on Jira Server.
+    fn token_pages<'a, T: IssueRepresentation>(
+        &'a self,
+        method: Method<'a>,
+        options: &'a RequestOptions,
+        paginate: bool,
+    ) -> BoxStream<'a, Result<JqlResults<T>, JiraQueryError>> {
+        // The state is the token and the position of the next page, or `None` after the last page.
+        stream::try_unfold(Some((None, 0)), move |state| async move {
+            let Some((token, start_at)) = state else {
+                return Ok(None);
+            };
+
+            let mut page = self
+                .enhanced_search(&method, options, token.as_deref())
+                .await?;
+            page.start_at = start_at;
+
+            let next = if paginate && !page.is_last_page() {
+                page.next_page_token
+                    .clone()
+                    .map(|token| (Some(token), page.fetched()))
+            } else {
+                None
+            };
+
+            Ok(Some((page, next)))
+        })
+        .boxed()
+    }
+
+    /// Whether the request downloads the results in a series of pages.
+    ///
+    /// Requests by keys always page through all results, regardless of the configured
+    /// pagination method, because Jira caps the page size. Otherwise, keys past the cap
+    /// would appear to be missing.
+    const fn paginates(&self, method: &Method) -> bool {
+        match (method, &self.pagination) {
+            (Method::Keys(_), _) | (_, Pagination::ChunkSize(_) | Pagination::Parallel { .. }) => {
+                true
+            }
+            (_, Pagination::Default | Pagination::MaxResults(_)) => false,
+        }
+    }
+
+    /// The largest number of keys that a single request can list on this deployment.
+    const fn keys_per_query(&self) -> usize {
+        match self.deployment {
+            Deployment::Server => MAX_KEYS_PER_QUERY,
+            Deployment::Cloud => MAX_KEYS_PER_BULK_FETCH,
+        }
+    }
+
+    /// Stream the individual issues from the pages of the request.
+    fn issue_stream<'a, T: IssueRepresentation>(
+        &'a self,
+        method: Method<'a>,
+        options: &'a RequestOptions,
+    ) -> BoxStream<'a, Result<T, JiraQueryError>> {
+        self.pages(method, options, self.paginates(&method))
+            .map_ok(|page| stream::iter(page.issues.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
     }
 
     /// Download a specific list (chunk) of issues.
     /// Reused elsewhere as a building block of different pagination methods.
-    async fn chunk_of_issues(
+    async fn chunk_of_issues<T: IssueRepresentation>(
         &self,
         method: &Method<'_>,
+        options: &RequestOptions,
         start_at: u32,
-    ) -> Result<Vec<Issue>, JiraQueryError> {
-        let url = self.path(method, start_at);
+    ) -> Result<JqlResults<T>, JiraQueryError> {
+        let url = self.path(method, options, start_at);
+
+        // Long queries, such as lists of many keys, exceed the URL length
+        // that servers and proxies accept. Send them in the body of a search instead.
+        // The Agile endpoints have no JQL that would preserve their scope in a search,
+        // so they always use GET.
+        let results: Result<JqlResults<T>, JiraQueryError> = match method.jql() {
+            Some(jql) if url.len() > MAX_URL_LENGTH => {
+                let body = self.search_body(jql, method, options, start_at);
+                self.post_json(&self.rest_url("search", &[]), &body).await
+            }
+            _ => self.get_json(&url).await,
+        };
+        let results = results.map_err(JiraQueryError::in_jql_context)?;
+
+        log::debug!("{results:#?}");
+
+        Ok(results)
+    }
+
+    /// Download a single page of a search from the Jira Cloud enhanced search endpoint.
+    async fn enhanced_search<T: IssueRepresentation>(
+        &self,
+        method: &Method<'_>,
+        options: &RequestOptions,
+        next_page_token: Option<&str>,
+    ) -> Result<JqlResults<T>, JiraQueryError> {
+        let jql = method.jql().unwrap_or_default();
+        let max_results = self.max_results();
+        // Unlike the older search, the enhanced search only returns issue IDs by default.
+        let fields = if options.fields.is_empty() {
+            vec!["*navigable".to_string()]
+        } else {
+            options.fields.clone()
+        };
+        let expand: Vec<&str> = options.expand.iter().map(|e| e.as_str()).collect();
+
+        let mut params = vec![("jql", jql.clone()), ("fields", fields.join(","))];
+        if let Some(token) = next_page_token {
+            params.push(("nextPageToken", token.to_string()));
+        }
+        if let Some(max_results) = max_results {
+            params.push(("maxResults", max_results.to_string()));
+        }
+        if !expand.is_empty() {
+            params.push(("expand", expand.join(",")));
+        }
+        if !options.properties.is_empty() {
+            params.push(("properties", options.properties.join(",")));
+        }
+        let url = self.rest_url("search/jql", &params);
+
+        let results: Result<JqlResults<T>, JiraQueryError> = if url.len() > MAX_URL_LENGTH {
+            let body = EnhancedSearchBody {
+                jql,
+                next_page_token,
+                max_results,
+                fields,
+                expand: (!expand.is_empty()).then(|| expand.join(",")),
+                properties: &options.properties,
+            };
+            self.post_json(&self.rest_url("search/jql", &[]), &body)
+                .await
+        } else {
+            self.get_json(&url).await
+        };
+        let results = results.map_err(JiraQueryError::in_jql_context)?;
+
+        log::debug!("{results:#?}");
+
+        Ok(results)
+    }
+
+    /// Download several issues by their keys from the Jira Cloud bulk fetch endpoint.
+    /// The result takes the form of a single page of search results.
+    async fn bulk_fetch<T: IssueRepresentation>(
+        &self,
+        keys: &[&str],
+        options: &RequestOptions,
+    ) -> Result<JqlResults<T>, JiraQueryError> {
+        let body = BulkFetchBody {
+            issue_ids_or_keys: keys,
+            fields: if options.fields.is_empty() {
+                vec!["*navigable".to_string()]
+            } else {
+                options.fields.clone()
+            },
+            expand: options.expand.iter().map(|e| e.as_str()).collect(),
+            properties: &options.properties,
+        };
 
-        let results = self
-            .authenticated_get(&url)
-            .await?
-            .json::<JqlResults>()
+        let results: BulkFetchResults<T> = self
+            .post_json(&self.rest_url("issue/bulkfetch", &[]), &body)
             .await?;
 
-        log::debug!("{:#?}", results);
+        log::debug!("{results:#?}");
+
+        let count = u32::try_from(results.issues.len()).unwrap_or(u32::MAX);
+
+        Ok(JqlResults {
+            start_at: 0,
+            max_results: count,
+            total: Some(count),
+            next_page_token: None,
+            is_last: Some(true),
+            issues: results.issues,
+            extra: Value::Null,
+        })
+    }
+
+    /// Access the complete history of changes to an issue, from the oldest change.
+    ///
+    /// Jira Cloud embeds only the most recent changes when you request an issue
+    /// with `Expand::Changelog`, so this method pages through the dedicated changelog endpoint.
+    /// Jira Server doesn't provide the endpoint, but embeds the complete changelog instead,
+    /// so on Server, this method falls back to the embedded changelog.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it.
+    pub async fn changelog(&self, key: &str) -> Result<Vec<ChangeHistory>, JiraQueryError> {
+        let endpoint = format!("issue/{}/changelog", segment(key));
+
+        match self
+            .paged_values(self.api_version.rest_prefix(), &endpoint, &[])
+            .await
+        {
+            Err(JiraQueryError::NotFound(_)) => {
+                log::debug!("The changelog endpoint is unavailable. Using the embedded changelog.");
+
+                // Skip all fields, which the changelog doesn't need.
+                let options = RequestOptions::new()
+                    .fields(&["-*all"])
+                    .expand(Expand::Changelog);
+                let issue: PartialIssue = self.issue_with(key, &options).await?;
+
+                Ok(issue
+                    .changelog
+                    .map(|changelog| changelog.histories)
+                    .unwrap_or_default())
+            }
+            result => result,
+        }
+    }
+
+    /// Access the metadata of all fields that the instance defines, including custom fields.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails or the response doesn't list the fields.
+    pub async fn fields(&self) -> Result<Vec<FieldDefinition>, JiraQueryError> {
+        self.get_json(&self.rest_url("field", &[])).await
+    }
+
+    /// Access the registry of all fields that the instance defines, which translates
+    /// field names to IDs.
+    ///
+    /// The first call downloads the fields. Subsequent calls reuse the registry.
+    ///
+    /// # Errors
+    ///
+    /// Fails if downloading the fields fails. The next call then tries again.
+    pub async fn field_registry(&self) -> Result<&FieldRegistry, JiraQueryError> {
+        self.field_registry
+            .get_or_try_init(|| async { self.fields().await.map(FieldRegistry::new) })
+            .await
+    }
+
+    /// Create an issue. The request must set at least the project, the issue type, and the summary.
+    ///
+    /// # Errors
+    ///
+    /// If Jira rejects the content of the issue, the `JiraQueryError::Http` error
+    /// lists the reasons for each field.
+    pub async fn create_issue(&self, issue: &IssueEdit) -> Result<CreatedIssue, JiraQueryError> {
+        let url = self.rest_url("issue", &[]);
+        self.write_json(&url, self.client.post(&url).json(issue))
+            .await
+    }
+
+    /// Edit the fields of an existing issue.
+    ///
+    /// # Errors
+    ///
+    /// If Jira rejects the changes, the `JiraQueryError::Http` error
+    /// lists the reasons for each field.
+    pub async fn update_issue(&self, key: &str, edit: &IssueEdit) -> Result<(), JiraQueryError> {
+        let url = self.rest_url(&format!("issue/{}", segment(key)), &[]);
+        self.send_write(self.client.put(&url).json(edit)).await?;
+        Ok(())
+    }
+
+    /// Access all comments below an issue, from the oldest comment.
+    ///
+    /// Unlike `Fields::comment`, which only contains the comments that Jira embeds
+    /// in the issue, this method pages through the complete list.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it.
+    pub async fn comments(&self, key: &str) -> Result<Vec<Comment>, JiraQueryError> {
+        let endpoint = format!("issue/{}/comment", segment(key));
+        self.paged_values(self.api_version.rest_prefix(), &endpoint, &[])
+            .await
+    }
+
+    /// Add a comment below an issue. With `visibility`, only the members
+    /// of the role or the group can see the comment.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::Http` if Jira rejects the comment, such as for an unknown role
+    /// or group, and with `JiraQueryError::NotFound` if the issue doesn't exist.
+    pub async fn add_comment(
+        &self,
+        key: &str,
+        body: impl Into<RichText>,
+        visibility: Option<&Visibility>,
+    ) -> Result<Comment, JiraQueryError> {
+        let comment = CommentBody {
+            body: edit::rich_text(body.into()),
+            visibility,
+        };
+        let url = self.rest_url(&format!("issue/{}/comment", segment(key)), &[]);
+        self.write_json(&url, self.client.post(&url).json(&comment))
+            .await
+    }
+
+    /// Replace the text of an existing comment. With `visibility`, also change
+    /// who can see the comment.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the comment doesn't exist,
+    /// and with `JiraQueryError::Forbidden` if the account can't edit it.
+    pub async fn update_comment(
+        &self,
+        key: &str,
+        id: &str,
+        body: impl Into<RichText>,
+        visibility: Option<&Visibility>,
+    ) -> Result<Comment, JiraQueryError> {
+        let comment = CommentBody {
+            body: edit::rich_text(body.into()),
+            visibility,
+        };
+        let url = self.rest_url(
+            &format!("issue/{}/comment/{}", segment(key), segment(id)),
+            &[],
+        );
+        self.write_json(&url, self.client.put(&url).json(&comment))
+            .await
+    }
+
+    /// Delete a comment.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the comment doesn't exist,
+    /// and with `JiraQueryError::Forbidden` if the account can't delete it.
+    pub async fn delete_comment(&self, key: &str, id: &str) -> Result<(), JiraQueryError> {
+        let url = self.rest_url(
+            &format!("issue/{}/comment/{}", segment(key), segment(id)),
+            &[],
+        );
+        self.send_write(self.client.delete(&url)).await?;
+        Ok(())
+    }
+
+    /// Access all worklogs of an issue, from the oldest worklog.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it.
+    pub async fn worklogs(&self, key: &str) -> Result<Vec<Worklog>, JiraQueryError> {
+        let endpoint = format!("issue/{}/worklog", segment(key));
+        self.paged_values(self.api_version.rest_prefix(), &endpoint, &[])
+            .await
+    }
+
+    /// Log time spent on an issue, and adjust the remaining estimate of the issue.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::Http` if Jira rejects the worklog or the estimate,
+    /// such as when time tracking is disabled.
+    pub async fn add_worklog(
+        &self,
+        key: &str,
+        entry: &WorklogEntry,
+        adjustment: &EstimateAdjustment,
+    ) -> Result<Worklog, JiraQueryError> {
+        let url = self.rest_url(
+            &format!("issue/{}/worklog", segment(key)),
+            &adjustment.params("reduceBy"),
+        );
+        self.write_json(&url, self.client.post(&url).json(entry))
+            .await
+    }
+
+    /// Replace an existing worklog, and adjust the remaining estimate of the issue.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::UnsupportedAdjustment` without sending the request
+    /// if the adjustment is `EstimateAdjustment::Manual`, which Jira doesn't support here.
+    /// Fails with `JiraQueryError::NotFound` if the worklog doesn't exist,
+    /// and with `JiraQueryError::Forbidden` if the account can't edit it.
+    pub async fn update_worklog(
+        &self,
+        key: &str,
+        id: &str,
+        entry: &WorklogEntry,
+        adjustment: &EstimateAdjustment,
+    ) -> Result<Worklog, JiraQueryError> {
+        if matches!(adjustment, EstimateAdjustment::Manual(_)) {
+            return Err(JiraQueryError::UnsupportedAdjustment);
+        }
+
+        let url = self.rest_url(
+            &format!("issue/{}/worklog/{}", segment(key), segment(id)),
+            &adjustment.params("reduceBy"),
+        );
+        self.write_json(&url, self.client.put(&url).json(entry))
+            .await
+    }
+
+    /// Delete a worklog, and adjust the remaining estimate of the issue.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the worklog doesn't exist,
+    /// and with `JiraQueryError::Forbidden` if the account can't delete it.
+    pub async fn delete_worklog(
+        &self,
+        key: &str,
+        id: &str,
+        adjustment: &EstimateAdjustment,
+    ) -> Result<(), JiraQueryError> {
+        let url = self.rest_url(
+            &format!("issue/{}/worklog/{}", segment(key), segment(id)),
+            &adjustment.params("increaseBy"),
+        );
+        self.send_write(self.client.delete(&url)).await?;
+        Ok(())
+    }
+
+    /// Access all worklogs in the instance that users logged or edited since the moment.
+    ///
+    /// To keep a copy of the worklogs in sync, pass the returned `until` moment
+    /// as `since` in the next call. Jira only reports worklogs that changed
+    /// more than a minute ago, so recent changes appear in the next call.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any request fails. No worklogs are returned in that case, so repeat the call
+    /// with the same `since` moment.
+    pub async fn updated_worklogs(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<UpdatedWorklogs, JiraQueryError> {
+        let mut ids = Vec::new();
+        let mut since = since.timestamp_millis();
+
+        loop {
+            let url = self.rest_url("worklog/updated", &[("since", since.to_string())]);
+            let changes: WorklogChanges = self.get_json(&url).await?;
+
+            ids.extend(changes.values.iter().map(|change| change.worklog_id));
+            since = changes.until;
+
+            if changes.last_page {
+                break;
+            }
+        }
+
+        let url = self.rest_url("worklog/list", &[]);
+        let mut worklogs = Vec::with_capacity(ids.len());
+        for ids_chunk in ids.chunks(MAX_WORKLOGS_PER_LIST) {
+            let mut chunk: Vec<Worklog> =
+                self.post_json(&url, &WorklogIds { ids: ids_chunk }).await?;
+            worklogs.append(&mut chunk);
+        }
+
+        Ok(UpdatedWorklogs {
+            worklogs,
+            until: worklog::from_millis(since),
+        })
+    }
+
+    /// Download the content of an attachment and write it to the writer, such as a file.
+    ///
+    /// The content streams in chunks, so it never needs to fit in memory.
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the attachment doesn't exist,
+    /// and with `JiraQueryError::Io` if writing to the writer fails.
+    pub async fn download_attachment<W: AsyncWrite + Unpin>(
+        &self,
+        attachment: &Attachment,
+        writer: &mut W,
+    ) -> Result<u64, JiraQueryError> {
+        let mut response = self.authenticated_get(&attachment.content).await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(JiraQueryError::from_response(status, &body));
+        }
+
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+
+        Ok(written)
+    }
+
+    /// Attach a file with the name and the content to an issue.
+    ///
+    /// Returns the list of new attachments, which contains the uploaded file.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::Http` if Jira rejects the file, such as when it exceeds
+    /// the size limit or attachments are disabled.
+    pub async fn upload_attachment(
+        &self,
+        key: &str,
+        filename: &str,
+        content: Vec<u8>,
+    ) -> Result<Vec<Attachment>, JiraQueryError> {
+        let part = reqwest::multipart::Part::bytes(content).file_name(filename.to_string());
+        let form = reqwest::multipart::Form::new().part("file", part);
+        let url = self.rest_url(&format!("issue/{}/attachments", segment(key)), &[]);
+        let request_builder = self
+            .client
+            .post(&url)
+            // Jira rejects uploads without this header as a protection against XSRF.
+            .header("X-Atlassian-Token", "no-check")
+            .multipart(form);
+
+        self.write_json(&url, request_builder).await
+    }
+
+    /// Access the types of links between issues that the instance defines, such as `Blocks`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails, such as when issue linking is disabled on the instance.
+    pub async fn link_types(&self) -> Result<Vec<IssueLinkType>, JiraQueryError> {
+        let link_types: IssueLinkTypes =
+            self.get_json(&self.rest_url("issueLinkType", &[])).await?;
+
+        Ok(link_types.issue_link_types)
+    }
+
+    /// Link two issues with the link type, by its name, so that `from` relates to `to`
+    /// in the outward direction of the type. For example, with the `Blocks` type,
+    /// `from` blocks `to`. Optionally, add a comment that explains the link.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if an issue doesn't exist,
+    /// and with `JiraQueryError::Http` if the instance doesn't define the link type.
+    pub async fn create_link(
+        &self,
+        from: &str,
+        to: &str,
+        link_type: &str,
+        comment: Option<RichText>,
+    ) -> Result<(), JiraQueryError> {
+        // Jira names the issues after the description that they display:
+        // the inward issue is the one that the outward description applies to.
+        let mut link = json!({
+            "type": { "name": link_type },
+            "inwardIssue": { "key": from },
+            "outwardIssue": { "key": to },
+        });
+        if let Some(comment) = comment {
+            link["comment"] = json!({ "body": edit::rich_text(comment) });
+        }
 
-        Ok(results.issues)
+        let url = self.rest_url("issueLink", &[]);
+        self.send_write(self.client.post(&url).json(&link)).await?;
+        Ok(())
+    }
+
+    /// Delete a link between issues, by the ID of the link.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the link doesn't exist.
+    pub async fn delete_link(&self, id: &str) -> Result<(), JiraQueryError> {
+        let url = self.rest_url(&format!("issueLink/{}", segment(id)), &[]);
+        self.send_write(self.client.delete(&url)).await?;
+        Ok(())
+    }
+
+    /// Access the links from an issue to external resources.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it.
+    pub async fn remote_links(&self, key: &str) -> Result<Vec<RemoteLink>, JiraQueryError> {
+        self.get_json(&self.rest_url(&format!("issue/{}/remotelink", segment(key)), &[]))
+            .await
+    }
+
+    /// Link an issue to an external resource. If the issue already links to a resource
+    /// with the same global ID, replace the existing link instead.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::Http` if Jira rejects the link, such as for a missing title.
+    pub async fn create_or_update_remote_link(
+        &self,
+        key: &str,
+        link: &RemoteLinkEdit,
+    ) -> Result<CreatedRemoteLink, JiraQueryError> {
+        let url = self.rest_url(&format!("issue/{}/remotelink", segment(key)), &[]);
+        self.write_json(&url, self.client.post(&url).json(link))
+            .await
+    }
+
+    /// Delete a link from an issue to an external resource, by the ID of the link.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the link doesn't exist.
+    pub async fn delete_remote_link(&self, key: &str, id: u64) -> Result<(), JiraQueryError> {
+        let url = self.rest_url(&format!("issue/{}/remotelink/{}", segment(key), id), &[]);
+        self.send_write(self.client.delete(&url)).await?;
+        Ok(())
+    }
+
+    /// Access the transitions that the workflow offers from the current status of an issue,
+    /// including the fields on their screens.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the issue doesn't exist or the account can't see it.
+    pub async fn transitions(&self, key: &str) -> Result<Vec<Transition>, JiraQueryError> {
+        let url = self.rest_url(
+            &format!("issue/{}/transitions", segment(key)),
+            &[("expand", "transitions.fields".to_string())],
+        );
+        let transitions: Transitions = self.get_json(&url).await?;
+
+        Ok(transitions.transitions)
+    }
+
+    /// Move an issue through a transition, such as to close it.
+    ///
+    /// # Errors
+    ///
+    /// If the workflow doesn't offer the transition from the current status of the issue,
+    /// fails with `JiraQueryError::TransitionUnavailable`, which lists the available transitions.
+    pub async fn transition(
+        &self,
+        key: &str,
+        request: &TransitionRequest,
+    ) -> Result<(), JiraQueryError> {
+        let transitions = self.transitions(key).await?;
+        let transition = transitions
+            .iter()
+            .find(|transition| request.matches(transition))
+            .ok_or_else(|| JiraQueryError::TransitionUnavailable {
+                key: key.to_string(),
+                transition: request.transition.clone(),
+                available: transitions.iter().map(|t| t.name.clone()).collect(),
+            })?;
+
+        let body = TransitionBody {
+            transition: TransitionId { id: &transition.id },
+            edit: &request.edit,
+        };
+        let url = self.rest_url(&format!("issue/{}/transitions", segment(key)), &[]);
+        self.send_write(self.client.post(&url).json(&body)).await?;
+
+        Ok(())
+    }
+
+    /// Walk the relations of the issues, such as links, subtasks, parents, and epics,
+    /// and collect the related issues in a graph.
+    ///
+    /// The walk starts at the issues with the keys and follows the relations that the options
+    /// select, up to the depth of the options. Each step downloads the newly found issues
+    /// in batches. Related issues that Jira doesn't return, such as because the account
+    /// can't see them, appear in `IssueGraph::missing`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a request fails. Related issues that Jira doesn't return aren't an error.
+    pub async fn issue_graph(
+        &self,
+        keys: &[&str],
+        options: &GraphOptions,
+    ) -> Result<IssueGraph, JiraQueryError> {
+        let epic_link = if options.follows_epics() {
+            self.field_registry()
+                .await?
+                .by_custom_type(graph::EPIC_LINK_TYPE)
+                .map(|field| field.id.clone())
+        } else {
+            None
+        };
+
+        let mut issues: BTreeMap<String, Issue> = BTreeMap::new();
+        let mut edges = Vec::new();
+        let mut missing = Vec::new();
+        let mut frontier: BTreeSet<String> = keys.iter().map(ToString::to_string).collect();
+        let mut depth = 0;
+
+        while !frontier.is_empty() {
+            let keys: Vec<&str> = frontier.iter().map(String::as_str).collect();
+            let found = self.issues_allow_missing(&keys).await?;
+            missing.extend(found.missing);
+
+            let mut next = BTreeSet::new();
+            for issue in found.issues {
+                let key = issue.key.clone();
+                let relations = graph::relations(&issue, options, epic_link.as_deref());
+
+                if depth < options.max_depth() {
+                    for edge in &relations {
+                        let other = if edge.from == key {
+                            &edge.to
+                        } else {
+                            &edge.from
+                        };
+                        next.insert(other.clone());
+                    }
+                    if options.follows_epics() && graph::is_epic(&issue) {
+                        for child in self.epic_children(&key).await? {
+                            next.insert(child.clone());
+                            edges.push(Edge {
+                                from: key.clone(),
+                                to: child,
+                                relation: Relation::Epic,
+                            });
+                        }
+                    }
+                }
+
+                edges.extend(relations);
+                issues.insert(key, issue);
+            }
+
+            next.retain(|key| !issues.contains_key(key) && !missing.contains(key));
+            frontier = next;
+            depth += 1;
+        }
+
+        Ok(IssueGraph::new(issues, edges, missing))
+    }
+
+    /// The keys of the issues in an epic. Without the Agile API, the epic has no issues.
+    async fn epic_children(&self, epic: &str) -> Result<Vec<String>, JiraQueryError> {
+        // Skip all fields, which the walk doesn't need.
+        let options = RequestOptions::new().fields(&["-*all"]);
+
+        match self.epic_issues_with::<PartialIssue>(epic, &options).await {
+            Ok(children) => Ok(children.into_iter().map(|child| child.key).collect()),
+            Err(JiraQueryError::NotFound(_)) => Ok(Vec::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Access all boards that the account can see.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a request fails, such as when the instance doesn't provide the Agile API.
+    pub async fn boards(&self) -> Result<Vec<Board>, JiraQueryError> {
+        self.paged_values(AGILE_PREFIX, "board", &[]).await
+    }
+
+    /// Access the configuration of a board, such as its columns and its filter.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the board doesn't exist or the account can't see it.
+    pub async fn board_configuration(
+        &self,
+        board_id: u64,
+    ) -> Result<BoardConfiguration, JiraQueryError> {
+        let url = self.agile_url(&format!("board/{board_id}/configuration"), &[]);
+        self.get_json(&url).await
+    }
+
+    /// Access the sprints of a board in the specified states.
+    /// If the list of states is empty, access all sprints.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::Http` if the board doesn't support sprints, such as a Kanban board.
+    pub async fn sprints(
+        &self,
+        board_id: u64,
+        states: &[SprintState],
+    ) -> Result<Vec<Sprint>, JiraQueryError> {
+        let mut params = Vec::new();
+        if !states.is_empty() {
+            let states: Vec<&str> = states.iter().map(|state| state.as_str()).collect();
+            params.push(("state", states.join(",")));
+        }
+
+        self.paged_values(AGILE_PREFIX, &format!("board/{board_id}/sprint"), &params)
+            .await
+    }
+
+    /// Access the epics of a board.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the board doesn't exist or the account can't see it.
+    pub async fn epics(&self, board_id: u64) -> Result<Vec<Epic>, JiraQueryError> {
+        self.paged_values(AGILE_PREFIX, &format!("board/{board_id}/epic"), &[])
+            .await
+    }
+
+    /// Access the issues in a sprint.
+    ///
+    /// The issues download in pages according to the configured pagination, like with `search`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the sprint doesn't exist or the account can't see it.
+    pub async fn sprint_issues(&self, sprint_id: u64) -> Result<Vec<Issue>, JiraQueryError> {
+        self.sprint_issues_with(sprint_id, &RequestOptions::new())
+            .await
+    }
+
+    /// Access the issues in a sprint, with options that control the content of the issues.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same cases as `sprint_issues`, and with `JiraQueryError::Deserialize`
+    /// if the response lacks a field that `T` requires.
+    pub async fn sprint_issues_with<T: IssueRepresentation>(
+        &self,
+        sprint_id: u64,
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        let endpoint = format!("sprint/{sprint_id}/issue");
+        self.all_issues(&Method::Agile(&endpoint), options).await
+    }
+
+    /// Access the issues in the backlog of a board.
+    ///
+    /// The issues download in pages according to the configured pagination, like with `search`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the board doesn't exist or the account can't see it.
+    pub async fn backlog_issues(&self, board_id: u64) -> Result<Vec<Issue>, JiraQueryError> {
+        self.backlog_issues_with(board_id, &RequestOptions::new())
+            .await
+    }
+
+    /// Access the issues in the backlog of a board, with options that control the content of the issues.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same cases as `backlog_issues`, and with `JiraQueryError::Deserialize`
+    /// if the response lacks a field that `T` requires.
+    pub async fn backlog_issues_with<T: IssueRepresentation>(
+        &self,
+        board_id: u64,
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        let endpoint = format!("board/{board_id}/backlog");
+        self.all_issues(&Method::Agile(&endpoint), options).await
+    }
+
+    /// Access the issues in an epic, which you can specify by its ID or key.
+    ///
+    /// The issues download in pages according to the configured pagination, like with `search`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::NotFound` if the epic doesn't exist or the account can't see it.
+    pub async fn epic_issues(&self, epic: &str) -> Result<Vec<Issue>, JiraQueryError> {
+        self.epic_issues_with(epic, &RequestOptions::new()).await
+    }
+
+    /// Access the issues in an epic, with options that control the content of the issues.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same cases as `epic_issues`, and with `JiraQueryError::Deserialize`
+    /// if the response lacks a field that `T` requires.
+    pub async fn epic_issues_with<T: IssueRepresentation>(
+        &self,
+        epic: &str,
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        let endpoint = format!("epic/{}/issue", segment(epic));
+        self.all_issues(&Method::Agile(&endpoint), options).await
     }
 
     /// Access issues using a free-form JQL search.
     ///
     /// An example of a query: `project="CentOS Stream" AND priority = High`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::InvalidJql` if Jira rejects the query.
+    /// A query that matches no issues isn't an error.
     pub async fn search(&self, query: &str) -> Result<Vec<Issue>, JiraQueryError> {
-        let method = Method::Search(query);
+        self.search_with(query, &RequestOptions::new()).await
+    }
 
-        // If Pagination is set to ChunkSize, split the issue keys into chunk by chunk size
-        // and request each chunk separately.
-        if let Pagination::ChunkSize(chunk_size) = self.pagination {
-            self.paginated_issues(&method, chunk_size).await
-        // If Pagination is not set to ChunkSize, use a single chunk request for all issues.
-        } else {
-            let issues = self.chunk_of_issues(&method, 0).await?;
+    /// Access issues using a free-form JQL search, with options that control the content of the issues.
+    ///
+    /// If the options reduce the set of fields, deserialize the issues as `PartialIssue`.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same cases as `search`, and with `JiraQueryError::Deserialize`
+    /// if the response lacks a field that `T` requires.
+    pub async fn search_with<T: IssueRepresentation>(
+        &self,
+        query: &str,
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        self.all_issues(&Method::Search(query), options).await
+    }
 
-            Ok(issues)
-        }
+    /// Access issues using a JQL query that you assemble with the `Jql` builder.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::InvalidJql` if Jira rejects the query,
+    /// such as when it refers to a field that doesn't exist.
+    pub async fn search_jql(&self, query: &Jql) -> Result<Vec<Issue>, JiraQueryError> {
+        self.search(&query.to_string()).await
+    }
+
+    /// Access issues using a JQL query that you assemble with the `Jql` builder,
+    /// with options that control the content of the issues.
+    ///
+    /// # Errors
+    ///
+    /// Fails in the same cases as `search_jql`, and with `JiraQueryError::Deserialize`
+    /// if the response lacks a field that `T` requires.
+    pub async fn search_jql_with<T: IssueRepresentation>(
+        &self,
+        query: &Jql,
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, JiraQueryError> {
+        self.search_with(&query.to_string(), options).await
+    }
+
+    /// Access issues using a free-form JQL search, as a stream of individual issues.
+    ///
+    /// With `Pagination::ChunkSize`, the stream downloads the next chunk only after
+    /// the consumer has processed all issues of the previous chunk. If the consumer
+    /// stops polling the stream early, the remaining chunks are never requested.
+    /// With `Pagination::Parallel`, the stream downloads up to `max_in_flight` chunks ahead.
+    /// With other pagination methods, the stream results from a single request.
+    #[must_use]
+    pub fn search_stream<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxStream<'a, Result<Issue, JiraQueryError>> {
+        self.issue_stream(Method::Search(query), &NO_OPTIONS)
+    }
+
+    /// Access issues using a free-form JQL search, as a stream of individual issues,
+    /// with options that control the content of the issues.
+    ///
+    /// The stream paginates the same way as `search_stream`.
+    #[must_use]
+    pub fn search_stream_with<'a, T: IssueRepresentation>(
+        &'a self,
+        query: &'a str,
+        options: &'a RequestOptions,
+    ) -> BoxStream<'a, Result<T, JiraQueryError>> {
+        self.issue_stream(Method::Search(query), options)
+    }
+
+    /// Access issues using a free-form JQL search, as a stream of pages.
+    ///
+    /// Each page carries the `startAt` and `total` values from Jira,
+    /// which enables you to report the progress of a long download.
+    /// The stream paginates the same way as `search_stream`.
+    #[must_use]
+    pub fn search_pages<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxStream<'a, Result<JqlResults, JiraQueryError>> {
+        self.pages(
+            Method::Search(query),
+            &NO_OPTIONS,
+            self.paginates(&Method::Search(query)),
+        )
+    }
+
+    /// Access several issues by their keys, as a stream of individual issues.
+    ///
+    /// The stream paginates the same way as `search_stream`, and requests a long list
+    /// of keys in several queries. Unlike `issues`, it doesn't report the requested keys
+    /// that Jira didn't return.
+    #[must_use]
+    pub fn issues_stream<'a>(
+        &'a self,
+        keys: &'a [&'a str],
+    ) -> BoxStream<'a, Result<Issue, JiraQueryError>> {
+        stream::iter(keys.chunks(self.keys_per_query()))
+            .flat_map(move |keys_chunk| self.issue_stream(Method::Keys(keys_chunk), &NO_OPTIONS))
+            .boxed()
     }
 }
 
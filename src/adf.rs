@@ -0,0 +1,374 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module models the Atlassian Document Format (ADF), which API v3 uses
+// for rich text fields such as the description or the body of a comment.
+// See https://developer.atlassian.com/cloud/jira/platform/apis/document/structure/
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::markup::WikiDocument;
+
+/// The content of a rich text field, such as the description of an issue.
+///
+/// API v2 returns rich text as a string in the Jira wiki markup.
+/// API v3 returns it as an Atlassian Document Format tree.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RichText {
+    WikiMarkup(String),
+    Adf(AdfNode),
+}
+
+impl RichText {
+    /// The text content without any formatting.
+    /// The text loses its formatting but keeps its block structure.
+    #[must_use]
+    pub fn to_plain_text(&self) -> String {
+        match self {
+            Self::WikiMarkup(markup) => WikiDocument::parse(markup).to_plain_text(),
+            Self::Adf(node) => node.to_plain_text(),
+        }
+    }
+}
+
+impl From<String> for RichText {
+    fn from(markup: String) -> Self {
+        Self::WikiMarkup(markup)
+    }
+}
+
+impl From<&str> for RichText {
+    fn from(markup: &str) -> Self {
+        Self::WikiMarkup(markup.to_string())
+    }
+}
+
+/// A single node in an Atlassian Document Format tree.
+///
+/// The root of every document is `Doc`. Nodes that this crate doesn't model
+/// deserialize as `Unknown` and contribute no text. Missing attributes and content
+/// fall back to empty values, so that a single unusual node doesn't fail the whole document.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AdfNode {
+    Doc {
+        #[serde(default)]
+        version: u32,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Paragraph {
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Heading {
+        #[serde(default)]
+        attrs: HeadingAttrs,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Text {
+        #[serde(default)]
+        text: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        marks: Vec<AdfMark>,
+    },
+    HardBreak,
+    Rule,
+    BulletList {
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    OrderedList {
+        attrs: Option<OrderedListAttrs>,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    ListItem {
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    CodeBlock {
+        attrs: Option<CodeBlockAttrs>,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Blockquote {
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Panel {
+        #[serde(default)]
+        attrs: Value,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Mention {
+        #[serde(default)]
+        attrs: MentionAttrs,
+    },
+    Emoji {
+        #[serde(default)]
+        attrs: EmojiAttrs,
+    },
+    InlineCard {
+        #[serde(default)]
+        attrs: CardAttrs,
+    },
+    Table {
+        attrs: Option<Value>,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    TableRow {
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    TableHeader {
+        attrs: Option<Value>,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    TableCell {
+        attrs: Option<Value>,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    MediaSingle {
+        attrs: Option<Value>,
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    MediaGroup {
+        #[serde(default)]
+        content: Vec<AdfNode>,
+    },
+    Media {
+        #[serde(default)]
+        attrs: MediaAttrs,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// Formatting that applies to a text node.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AdfMark {
+    Strong,
+    Em,
+    Code,
+    Strike,
+    Underline,
+    Link {
+        #[serde(default)]
+        attrs: LinkAttrs,
+    },
+    TextColor {
+        #[serde(default)]
+        attrs: Value,
+    },
+    Subsup {
+        #[serde(default)]
+        attrs: Value,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// The level of a heading, from 1 to 6.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HeadingAttrs {
+    pub level: u8,
+}
+
+/// The number of the first item in an ordered list.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct OrderedListAttrs {
+    pub order: Option<u32>,
+}
+
+/// The programming language of a code block.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CodeBlockAttrs {
+    pub language: Option<String>,
+}
+
+/// The user that a mention refers to.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MentionAttrs {
+    pub id: String,
+    /// The display name of the user, including the leading `@`.
+    pub text: Option<String>,
+    #[serde(rename = "accessLevel")]
+    pub access_level: Option<String>,
+}
+
+/// An emoji, identified by its short name such as `:smile:`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct EmojiAttrs {
+    #[serde(rename = "shortName")]
+    pub short_name: String,
+    pub id: Option<String>,
+    pub text: Option<String>,
+}
+
+/// The target of an inline link card.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CardAttrs {
+    pub url: Option<String>,
+    pub data: Option<Value>,
+}
+
+/// The target of a hyperlink.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LinkAttrs {
+    pub href: String,
+    pub title: Option<String>,
+}
+
+/// A reference to an attachment or another file in the media service,
+/// or to an external image.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MediaAttrs {
+    /// The ID of the file in the media service. External media have none.
+    pub id: Option<String>,
+    /// The kind of media, such as `file`, `link`, or `external`.
+    #[serde(rename = "type")]
+    pub media_type: String,
+    pub collection: Option<String>,
+    /// The address of external media.
+    pub url: Option<String>,
+    pub alt: Option<String>,
+}
+
+impl AdfNode {
+    /// The text content of this node and its descendants, without any formatting.
+    ///
+    /// Blocks are separated by an empty line, list items start on a new line,
+    /// and table cells are separated by a tab.
+    #[must_use]
+    pub fn to_plain_text(&self) -> String {
+        match self {
+            Self::Doc { content, .. }
+            | Self::Blockquote { content }
+            | Self::Panel { content, .. }
+            | Self::ListItem { content } => join_blocks(content, "\n\n"),
+            Self::Paragraph { content }
+            | Self::Heading { content, .. }
+            | Self::CodeBlock { content, .. }
+            | Self::TableHeader { content, .. }
+            | Self::TableCell { content, .. } => content.iter().map(Self::to_plain_text).collect(),
+            Self::Text { text, .. } => text.clone(),
+            Self::HardBreak => "\n".to_string(),
+            Self::Mention { attrs } => attrs
+                .text
+                .clone()
+                .unwrap_or_else(|| format!("@{}", attrs.id)),
+            Self::Emoji { attrs } => attrs
+                .text
+                .clone()
+                .unwrap_or_else(|| attrs.short_name.clone()),
+            Self::InlineCard { attrs } => attrs.url.clone().unwrap_or_default(),
+            Self::BulletList { content } => content
+                .iter()
+                .map(|item| format!("- {}", item.to_plain_text()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::OrderedList { attrs, content } => {
+                let first = attrs.as_ref().and_then(|a| a.order).unwrap_or(1);
+                (first..)
+                    .zip(content)
+                    .map(|(number, item)| format!("{number}. {}", item.to_plain_text()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Self::Table { content, .. } => join_blocks(content, "\n"),
+            Self::TableRow { content } => join_blocks(content, "\t"),
+            Self::MediaSingle { content, .. } | Self::MediaGroup { content } => {
+                join_blocks(content, "\n")
+            }
+            Self::Media { attrs } => attrs.alt.clone().unwrap_or_default(),
+            Self::Rule | Self::Unknown => String::new(),
+        }
+    }
+}
+
+/// Convert the nodes to plain text and join the non-empty results with the separator.
+fn join_blocks(nodes: &[AdfNode], separator: &str) -> String {
+    nodes
+        .iter()
+        .map(AdfNode::to_plain_text)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adf_to_plain_text() {
+        let text: RichText =
+            serde_json::from_str(include_str!("../tests/fixtures/description.adf.json")).unwrap();
+
+        assert!(matches!(text, RichText::Adf(AdfNode::Doc { .. })));
+        assert_eq!(
+            text.to_plain_text(),
+            "Steps to reproduce\n\n\
+             Open the page as @Jane Doe and click Save.\n\
+             The page freezes :cry:\n\n\
+             - First item\n\
+             - Second item\n\n\
+             systemctl restart app\n\n\
+             Architecture diagram\n\n\
+             Name\tValue\n\
+             retries\t3"
+        );
+    }
+
+    #[test]
+    fn tolerate_incomplete_nodes() {
+        let text: RichText = serde_json::from_str(
+            r#"{"type": "doc", "content": [
+                {"type": "heading", "content": [{"type": "text", "text": "Title"}]},
+                {"type": "paragraph", "content": [{"type": "mention", "attrs": {"text": "@Jane"}}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(text.to_plain_text(), "Title\n\n@Jane");
+    }
+
+    #[test]
+    fn wiki_markup_stays_a_string() {
+        let text: RichText = serde_json::from_str(r#""h1. Title""#).unwrap();
+
+        assert_eq!(text, RichText::WikiMarkup("h1. Title".to_string()));
+        assert_eq!(text.to_plain_text(), "Title");
+    }
+}
@@ -0,0 +1,129 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module replicates the boards and epics of the Jira Software (Agile) API
+// as strongly typed structs. Sprints belong to the issue model,
+// because issues refer to them in the sprint custom field.
+// See https://docs.atlassian.com/jira-software/REST/latest/
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A Scrum or Kanban board.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Board {
+    pub id: u64,
+    pub name: String,
+    /// The kind of board, such as `scrum` or `kanban`.
+    #[serde(rename = "type")]
+    pub board_type: String,
+    pub location: Option<BoardLocation>,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The project or user that a board belongs to.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BoardLocation {
+    #[serde(rename = "projectId")]
+    pub project_id: Option<u64>,
+    #[serde(rename = "projectKey")]
+    pub project_key: Option<String>,
+    #[serde(rename = "projectName")]
+    pub project_name: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The configuration of a board, which maps statuses to columns
+/// and selects the issues on the board with a filter.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BoardConfiguration {
+    pub id: u64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub board_type: String,
+    pub filter: BoardFilter,
+    #[serde(rename = "columnConfig")]
+    pub column_config: ColumnConfig,
+    /// The field that estimates the issues, such as story points.
+    pub estimation: Option<Value>,
+    /// The field that ranks the issues.
+    pub ranking: Option<Value>,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The saved filter that selects the issues on a board.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BoardFilter {
+    pub id: String,
+    #[serde(rename = "self")]
+    pub self_link: String,
+}
+
+/// The columns of a board.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColumnConfig {
+    pub columns: Vec<BoardColumn>,
+    /// The measure that limits the number of issues in a column, such as `issueCount`.
+    #[serde(rename = "constraintType")]
+    pub constraint_type: Option<String>,
+}
+
+/// A single column of a board and the statuses that it contains.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BoardColumn {
+    pub name: String,
+    pub statuses: Vec<ColumnStatus>,
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+/// A reference to a status in a board column.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ColumnStatus {
+    pub id: String,
+    #[serde(rename = "self")]
+    pub self_link: String,
+}
+
+/// An epic, as the Agile API represents it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Epic {
+    pub id: u64,
+    pub key: String,
+    pub name: String,
+    pub summary: String,
+    pub done: bool,
+    pub color: Option<EpicColor>,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The color that identifies an epic on a board, such as `color_4`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EpicColor {
+    pub key: String,
+}
@@ -0,0 +1,524 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// Time-in-status and cycle-time analytics computed from issue changelogs.
+//
+// The computation works offline on issues that you've already downloaded
+// with `Expand::Changelog`. Jira Cloud embeds only the most recent changes of a long
+// changelog in the issue. For such issues, access the complete history
+// with `JiraInstance::changelog` and pass it to `IssueMetrics::from_histories`.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::issue_model::{ChangeHistory, Issue};
+
+/// The status names that determine the stages of a workflow.
+///
+/// The changelog records statuses only by name, without their category,
+/// so the analytics need to know which names mean that work started or finished.
+/// The names are compared case-insensitively.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Workflow {
+    /// The statuses that mark active work on the issue.
+    pub in_progress: Vec<String>,
+    /// The statuses that mark the issue as finished.
+    pub done: Vec<String>,
+}
+
+impl Default for Workflow {
+    /// The status names of the default Jira workflows.
+    fn default() -> Self {
+        Self {
+            in_progress: vec!["In Progress".to_string()],
+            done: vec![
+                "Done".to_string(),
+                "Closed".to_string(),
+                "Resolved".to_string(),
+            ],
+        }
+    }
+}
+
+impl Workflow {
+    fn is_in_progress(&self, status: &str) -> bool {
+        self.in_progress
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(status))
+    }
+
+    fn is_done(&self, status: &str) -> bool {
+        self.done
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(status))
+    }
+}
+
+/// A continuous period that an issue spent in a single status.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusInterval {
+    pub status: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl StatusInterval {
+    /// The time that the issue spent in the status during this interval.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// The workflow metrics of a single issue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IssueMetrics {
+    pub key: String,
+    /// The statuses of the issue in chronological order, from its creation
+    /// until its resolution, or until now if it's unresolved.
+    pub intervals: Vec<StatusInterval>,
+    /// The total time that the issue spent in each status.
+    pub time_in_status: BTreeMap<String, Duration>,
+    /// When the issue first entered an in-progress status.
+    pub first_in_progress: Option<DateTime<Utc>>,
+    /// How many times the issue moved from a done status back to an unfinished one.
+    pub reopen_count: u32,
+    /// The lead time from the creation of the issue to its resolution.
+    pub resolution_time: Option<Duration>,
+    /// The cycle time from the first in-progress status to the resolution.
+    pub cycle_time: Option<Duration>,
+    /// Whether the metrics cover the complete history of the issue.
+    ///
+    /// `from_issue` sets it to `false` when the `total` of the embedded changelog exceeds
+    /// the number of its `histories`, which means that Jira left out some changes
+    /// and the metrics miss their status transitions. In all other cases, it's `true`.
+    pub complete_history: bool,
+}
+
+impl IssueMetrics {
+    /// Compute the metrics from the changelog embedded in the issue.
+    ///
+    /// The `now` argument marks the end of the last interval of an unresolved issue.
+    /// If the issue carries no changelog, the metrics assume that the status never changed.
+    ///
+    /// If the embedded changelog is incomplete, as described in `complete_history`,
+    /// download the complete history with `JiraInstance::changelog`
+    /// and pass it to `from_histories`.
+    #[must_use]
+    pub fn from_issue(issue: &Issue, workflow: &Workflow, now: DateTime<Utc>) -> Self {
+        let histories = issue
+            .changelog
+            .as_ref()
+            .map_or(&[][..], |changelog| &changelog.histories[..]);
+
+        let mut metrics = Self::from_histories(issue, histories, workflow, now);
+        metrics.complete_history = issue.changelog.as_ref().map_or(true, |changelog| {
+            u32::try_from(changelog.histories.len()).map_or(true, |len| len >= changelog.total)
+        });
+        metrics
+    }
+
+    /// Compute the metrics from a separately downloaded history of the issue,
+    /// such as the result of `JiraInstance::changelog`.
+    ///
+    /// The `now` argument marks the end of the last interval of an unresolved issue.
+    /// The metrics assume that the histories are complete.
+    #[must_use]
+    pub fn from_histories(
+        issue: &Issue,
+        histories: &[ChangeHistory],
+        workflow: &Workflow,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let created = issue.fields.created;
+        let resolved = issue.fields.resolutiondate;
+        // Guard against clocks that place the end before the creation.
+        let end = resolved.unwrap_or(now).max(created);
+
+        // The status transitions in chronological order, as (time, from, to).
+        let mut transitions: Vec<(DateTime<Utc>, Option<&str>, &str)> = histories
+            .iter()
+            .flat_map(|history| {
+                history
+                    .items
+                    .iter()
+                    .filter(|item| item.field == "status")
+                    .filter_map(move |item| {
+                        item.to_string
+                            .as_deref()
+                            .map(|to| (history.created, item.from_string.as_deref(), to))
+                    })
+            })
+            .collect();
+        transitions.sort_by_key(|(time, _, _)| *time);
+
+        // The issue starts in the status that the first transition leaves,
+        // or in its current status if it never changed.
+        let initial_status = transitions
+            .first()
+            .and_then(|(_, from, _)| *from)
+            .unwrap_or(&issue.fields.status.name);
+
+        let mut intervals = Vec::new();
+        let mut status = initial_status;
+        let mut since = created;
+        let mut first_in_progress = None;
+        let mut reopen_count = 0;
+
+        if workflow.is_in_progress(status) {
+            first_in_progress = Some(created);
+        }
+
+        for (time, _, to) in transitions {
+            // Ignore changes after the end, such as edits of a resolved issue.
+            let time = time.clamp(created, end);
+
+            intervals.push(StatusInterval {
+                status: status.to_string(),
+                start: since,
+                end: time,
+            });
+
+            if workflow.is_done(status) && !workflow.is_done(to) {
+                reopen_count += 1;
+            }
+            if first_in_progress.is_none() && workflow.is_in_progress(to) {
+                first_in_progress = Some(time);
+            }
+
+            status = to;
+            since = time;
+        }
+
+        intervals.push(StatusInterval {
+            status: status.to_string(),
+            start: since,
+            end: end.max(since),
+        });
+
+        let mut time_in_status = BTreeMap::new();
+        for interval in &intervals {
+            *time_in_status
+                .entry(interval.status.clone())
+                .or_insert_with(Duration::zero) += interval.duration();
+        }
+
+        Self {
+            key: issue.key.clone(),
+            intervals,
+            time_in_status,
+            first_in_progress,
+            reopen_count,
+            resolution_time: resolved.map(|resolved| resolved - created),
+            cycle_time: resolved
+                .zip(first_in_progress)
+                .map(|(resolved, started)| resolved - started),
+            complete_history: true,
+        }
+    }
+
+    /// Compute the metrics of several issues from their embedded changelogs.
+    ///
+    /// Check `complete_history` in the results for issues with an incomplete changelog.
+    #[must_use]
+    pub fn for_issues(issues: &[Issue], workflow: &Workflow, now: DateTime<Utc>) -> Vec<Self> {
+        issues
+            .iter()
+            .map(|issue| Self::from_issue(issue, workflow, now))
+            .collect()
+    }
+}
+
+/// The workflow metrics summarized over a group of issues.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggregateMetrics {
+    /// The number of issues in the group.
+    pub issues: usize,
+    /// The time that all issues together spent in each status.
+    pub total_time_in_status: BTreeMap<String, Duration>,
+    /// The average time per issue in each status, over the issues that visited the status.
+    pub mean_time_in_status: BTreeMap<String, Duration>,
+    /// The number of reopens over all issues.
+    pub total_reopens: u32,
+    /// The statistics of the lead time, over the resolved issues.
+    pub resolution_time: Option<DurationStats>,
+    /// The statistics of the cycle time, over the resolved issues that were in progress.
+    pub cycle_time: Option<DurationStats>,
+    /// The number of issues whose metrics don't cover their complete history,
+    /// as `IssueMetrics::complete_history` reports.
+    pub incomplete_histories: usize,
+}
+
+/// Summary statistics of a list of durations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DurationStats {
+    /// The number of durations that the statistics cover.
+    pub count: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl DurationStats {
+    /// Compute the statistics, or `None` if the list is empty.
+    #[must_use]
+    pub fn from_durations(durations: &[Duration]) -> Option<Self> {
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+
+        let count = sorted.len();
+        let min = *sorted.first()?;
+        let max = *sorted.last()?;
+        let median = if count % 2 == 0 {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2
+        } else {
+            sorted[count / 2]
+        };
+
+        Some(Self {
+            count,
+            mean: mean(&sorted),
+            median,
+            min,
+            max,
+        })
+    }
+}
+
+impl AggregateMetrics {
+    /// Summarize the metrics of several issues.
+    #[must_use]
+    pub fn from_metrics(metrics: &[IssueMetrics]) -> Self {
+        let mut per_status: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        for issue in metrics {
+            for (status, duration) in &issue.time_in_status {
+                per_status
+                    .entry(status.clone())
+                    .or_default()
+                    .push(*duration);
+            }
+        }
+
+        let total_time_in_status = per_status
+            .iter()
+            .map(|(status, durations)| (status.clone(), durations.iter().copied().sum()))
+            .collect();
+        let mean_time_in_status = per_status
+            .iter()
+            .map(|(status, durations)| (status.clone(), mean(durations)))
+            .collect();
+
+        let resolution_times: Vec<Duration> =
+            metrics.iter().filter_map(|m| m.resolution_time).collect();
+        let cycle_times: Vec<Duration> = metrics.iter().filter_map(|m| m.cycle_time).collect();
+
+        Self {
+            issues: metrics.len(),
+            total_time_in_status,
+            mean_time_in_status,
+            total_reopens: metrics.iter().map(|m| m.reopen_count).sum(),
+            resolution_time: DurationStats::from_durations(&resolution_times),
+            cycle_time: DurationStats::from_durations(&cycle_times),
+            incomplete_histories: metrics.iter().filter(|m| !m.complete_history).count(),
+        }
+    }
+
+    /// Compute and summarize the metrics of several issues from their embedded changelogs.
+    ///
+    /// The `incomplete_histories` count reports the issues with an incomplete changelog.
+    /// For accurate results, download their complete histories with
+    /// `JiraInstance::changelog` and summarize the metrics with `from_metrics`.
+    #[must_use]
+    pub fn from_issues(issues: &[Issue], workflow: &Workflow, now: DateTime<Utc>) -> Self {
+        Self::from_metrics(&IssueMetrics::for_issues(issues, workflow, now))
+    }
+}
+
+/// The arithmetic mean of the durations, or zero if the list is empty.
+fn mean(durations: &[Duration]) -> Duration {
+    let Ok(count) = i32::try_from(durations.len()) else {
+        return Duration::zero();
+    };
+    if count == 0 {
+        return Duration::zero();
+    }
+
+    durations.iter().copied().sum::<Duration>() / count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The shared issue fixture, created on 2022-05-24 at 10:00,
+    /// with the specified status transitions as (time, from, to).
+    fn issue_with_transitions(transitions: &[(&str, &str, &str)], resolved: Option<&str>) -> Issue {
+        let mut issue: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/issue.json")).unwrap();
+
+        let histories: Vec<serde_json::Value> = transitions
+            .iter()
+            .enumerate()
+            .map(|(index, (created, from, to))| {
+                json!({
+                    "id": index.to_string(),
+                    "created": created,
+                    "items": [{
+                        "field": "status",
+                        "fieldtype": "jira",
+                        "fromString": from,
+                        "toString": to,
+                    }],
+                })
+            })
+            .collect();
+
+        issue["changelog"] = json!({
+            "startAt": 0,
+            "maxResults": histories.len(),
+            "total": histories.len(),
+            "histories": histories,
+        });
+        issue["fields"]["resolutiondate"] = json!(resolved);
+
+        serde_json::from_value(issue).unwrap()
+    }
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn resolved_issue() {
+        let issue = issue_with_transitions(
+            &[
+                ("2022-05-24T12:00:00.000+0000", "Open", "In Progress"),
+                ("2022-05-25T12:00:00.000+0000", "In Progress", "Closed"),
+            ],
+            Some("2022-05-25T12:00:00.000+0000"),
+        );
+        let metrics =
+            IssueMetrics::from_issue(&issue, &Workflow::default(), time("2022-06-01T00:00:00Z"));
+
+        assert_eq!(metrics.time_in_status["Open"], Duration::hours(2));
+        assert_eq!(metrics.time_in_status["In Progress"], Duration::hours(24));
+        assert_eq!(metrics.time_in_status["Closed"], Duration::zero());
+        assert_eq!(
+            metrics.first_in_progress,
+            Some(time("2022-05-24T12:00:00Z"))
+        );
+        assert_eq!(metrics.resolution_time, Some(Duration::hours(26)));
+        assert_eq!(metrics.cycle_time, Some(Duration::hours(24)));
+        assert_eq!(metrics.reopen_count, 0);
+    }
+
+    #[test]
+    fn reopened_issue() {
+        let issue = issue_with_transitions(
+            &[
+                ("2022-05-24T11:00:00.000+0000", "Open", "Closed"),
+                ("2022-05-24T12:00:00.000+0000", "Closed", "Open"),
+                ("2022-05-24T14:00:00.000+0000", "Open", "In Progress"),
+            ],
+            None,
+        );
+        let metrics =
+            IssueMetrics::from_issue(&issue, &Workflow::default(), time("2022-05-24T20:00:00Z"));
+
+        assert_eq!(metrics.reopen_count, 1);
+        assert_eq!(metrics.intervals.len(), 4);
+        // Open before closing, and again after reopening.
+        assert_eq!(metrics.time_in_status["Open"], Duration::hours(3));
+        // The unresolved issue stays in progress until now.
+        assert_eq!(metrics.time_in_status["In Progress"], Duration::hours(6));
+        assert_eq!(metrics.resolution_time, None);
+        assert_eq!(metrics.cycle_time, None);
+    }
+
+    #[test]
+    fn issue_without_changelog() {
+        let mut issue = issue_with_transitions(&[], None);
+        issue.changelog = None;
+        let metrics =
+            IssueMetrics::from_issue(&issue, &Workflow::default(), time("2022-05-25T10:00:00Z"));
+
+        assert_eq!(metrics.time_in_status["Open"], Duration::hours(24));
+        assert_eq!(metrics.first_in_progress, None);
+    }
+
+    #[test]
+    fn truncated_changelog() {
+        let mut issue = issue_with_transitions(
+            &[("2022-05-24T12:00:00.000+0000", "Open", "In Progress")],
+            None,
+        );
+        let now = time("2022-05-25T10:00:00Z");
+        let metrics = IssueMetrics::from_issue(&issue, &Workflow::default(), now);
+        assert!(metrics.complete_history);
+
+        issue.changelog.as_mut().unwrap().total = 150;
+        let metrics = IssueMetrics::from_issue(&issue, &Workflow::default(), now);
+        assert!(!metrics.complete_history);
+        assert_eq!(
+            AggregateMetrics::from_metrics(&[metrics]).incomplete_histories,
+            1
+        );
+    }
+
+    #[test]
+    fn aggregate() {
+        let quick = issue_with_transitions(
+            &[
+                ("2022-05-24T11:00:00.000+0000", "Open", "In Progress"),
+                ("2022-05-24T12:00:00.000+0000", "In Progress", "Done"),
+            ],
+            Some("2022-05-24T12:00:00.000+0000"),
+        );
+        let slow = issue_with_transitions(
+            &[
+                ("2022-05-24T11:00:00.000+0000", "Open", "In Progress"),
+                ("2022-05-24T14:00:00.000+0000", "In Progress", "Done"),
+            ],
+            Some("2022-05-24T14:00:00.000+0000"),
+        );
+        let summary = AggregateMetrics::from_issues(
+            &[quick, slow],
+            &Workflow::default(),
+            time("2022-06-01T00:00:00Z"),
+        );
+
+        assert_eq!(summary.issues, 2);
+        assert_eq!(
+            summary.total_time_in_status["In Progress"],
+            Duration::hours(4)
+        );
+        assert_eq!(
+            summary.mean_time_in_status["In Progress"],
+            Duration::hours(2)
+        );
+
+        let cycle_time = summary.cycle_time.unwrap();
+        assert_eq!(cycle_time.count, 2);
+        assert_eq!(cycle_time.median, Duration::hours(2));
+        assert_eq!(cycle_time.min, Duration::hours(1));
+        assert_eq!(cycle_time.max, Duration::hours(3));
+    }
+}
@@ -0,0 +1,316 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module converts the values of custom fields, which the issue model keeps
+// as untyped JSON, to the types that match the standard custom field schemas.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::errors::JiraQueryError;
+use crate::fields::FieldRegistry;
+use crate::issue_model::{Sprint, User, Version};
+
+/// A type that the value of a custom field can convert to.
+///
+/// The crate implements the trait for the standard Jira custom field schemas:
+///
+/// * Number: `f64`
+/// * Text or select list of strings: `String`
+/// * Date picker: `NaiveDate`
+/// * Date time picker: `DateTime<Utc>`
+/// * URL: `Url`
+/// * Single select or radio buttons: `SelectOption`
+/// * Cascading select: `CascadingSelect`
+/// * User picker: `User`
+/// * Version picker: `Version`
+/// * Sprint: `Sprint`, from a JSON object or a legacy string
+/// * Multi select, checkboxes, multi-user picker, multi-version picker, and labels:
+///   `Vec` of the item type
+pub trait FromCustomField: Sized {
+    /// A description of the expected value, such as `a number`, for error messages.
+    fn expected() -> String;
+
+    /// Convert the JSON value of a custom field, or return `None` if the value doesn't match.
+    fn from_custom_field(value: &Value) -> Option<Self>;
+}
+
+/// An option of a select list, radio buttons, or checkboxes.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SelectOption {
+    pub id: String,
+    pub value: String,
+    pub disabled: Option<bool>,
+    #[serde(rename = "self")]
+    pub self_link: Option<String>,
+}
+
+/// The selection in a cascading select list, which consists of a parent option
+/// and an optional child option.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CascadingSelect {
+    pub parent: SelectOption,
+    pub child: Option<SelectOption>,
+}
+
+/// Convert the value using its `Deserialize` implementation.
+fn deserialize<T: DeserializeOwned>(value: &Value) -> Option<T> {
+    T::deserialize(value).ok()
+}
+
+impl FromCustomField for f64 {
+    fn expected() -> String {
+        "a number".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        value.as_f64()
+    }
+}
+
+impl FromCustomField for String {
+    fn expected() -> String {
+        "a string".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        value.as_str().map(ToString::to_string)
+    }
+}
+
+impl FromCustomField for NaiveDate {
+    fn expected() -> String {
+        "a date".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        deserialize(value)
+    }
+}
+
+impl FromCustomField for DateTime<Utc> {
+    fn expected() -> String {
+        "a date and time".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        deserialize(value)
+    }
+}
+
+impl FromCustomField for Url {
+    fn expected() -> String {
+        "a URL".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        value.as_str().and_then(|url| Url::parse(url).ok())
+    }
+}
+
+impl FromCustomField for SelectOption {
+    fn expected() -> String {
+        "a select option".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        deserialize(value)
+    }
+}
+
+impl FromCustomField for CascadingSelect {
+    fn expected() -> String {
+        "a cascading select option".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        // Jira nests the child option in the parent option.
+        let child = match value.get("child") {
+            None | Some(Value::Null) => None,
+            Some(child) => Some(deserialize(child)?),
+        };
+
+        Some(Self {
+            parent: deserialize(value)?,
+            child,
+        })
+    }
+}
+
+impl FromCustomField for User {
+    fn expected() -> String {
+        "a user".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        deserialize(value)
+    }
+}
+
+impl FromCustomField for Version {
+    fn expected() -> String {
+        "a version".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        deserialize(value)
+    }
+}
+
+impl FromCustomField for Sprint {
+    fn expected() -> String {
+        "a sprint".to_string()
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        deserialize(value)
+    }
+}
+
+impl<T: FromCustomField> FromCustomField for Vec<T> {
+    fn expected() -> String {
+        format!("an array of items, each {}", T::expected())
+    }
+
+    fn from_custom_field(value: &Value) -> Option<Self> {
+        value.as_array()?.iter().map(T::from_custom_field).collect()
+    }
+}
+
+/// Access to the custom fields of an issue, which Jira returns alongside the standard fields.
+///
+/// The crate implements the trait for `Issue` and `PartialIssue`, and for their `fields`.
+/// A partial issue only contains the fields that you requested in `RequestOptions::fields`.
+pub trait CustomFields {
+    /// The fields that the issue model doesn't recognize, indexed by their IDs.
+    fn extra_fields(&self) -> &Value;
+
+    /// The value of the custom field with the display name, such as `Story Points`.
+    ///
+    /// Returns `None` if the instance doesn't define the field, or if the issue doesn't set it.
+    fn custom_field_by_name(&self, registry: &FieldRegistry, name: &str) -> Option<&Value> {
+        let id = registry.id_of(name)?;
+        self.extra_fields().get(id).filter(|value| !value.is_null())
+    }
+
+    /// Convert the custom field with the ID, such as `customfield_12345`, to the type.
+    ///
+    /// Returns `None` if the issue doesn't contain the field or doesn't set it.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::CustomFieldType` if the value doesn't match the type.
+    fn custom_field<T: FromCustomField>(&self, id: &str) -> Result<Option<T>, JiraQueryError> {
+        extract(self.extra_fields(), id)
+    }
+
+    /// Convert the custom field with the display name, such as `Story Points`, to the type.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::UnknownField` if the instance doesn't define the field,
+    /// and with `JiraQueryError::CustomFieldType` if the value doesn't match the type.
+    fn named_custom_field<T: FromCustomField>(
+        &self,
+        registry: &FieldRegistry,
+        name: &str,
+    ) -> Result<Option<T>, JiraQueryError> {
+        let id = registry
+            .id_of(name)
+            .ok_or_else(|| JiraQueryError::UnknownField(name.to_string()))?;
+        self.custom_field(id)
+    }
+}
+
+/// Convert the custom field with the ID from the extra fields of an issue.
+/// A missing field or a field without a value results in `None`.
+pub(crate) fn extract<T: FromCustomField>(
+    extra: &Value,
+    id: &str,
+) -> Result<Option<T>, JiraQueryError> {
+    match extra.get(id) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => {
+            T::from_custom_field(value)
+                .map(Some)
+                .ok_or_else(|| JiraQueryError::CustomFieldType {
+                    field: id.to_string(),
+                    expected: T::expected(),
+                    value: value.clone(),
+                })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn standard_schemas() {
+        let extra = json!({
+            "customfield_1": 5.0,
+            "customfield_2": "2022-05-24",
+            "customfield_3": [{ "id": "1", "value": "Linux" }, { "id": "2", "value": "macOS" }],
+            "customfield_4": { "id": "10", "value": "Hardware", "child": { "id": "11", "value": "Disk" } },
+            "customfield_5": "https://example.com/docs",
+            "customfield_6": null,
+        });
+
+        assert_eq!(extract::<f64>(&extra, "customfield_1").unwrap(), Some(5.0));
+        assert_eq!(
+            extract::<NaiveDate>(&extra, "customfield_2").unwrap(),
+            NaiveDate::from_ymd_opt(2022, 5, 24)
+        );
+
+        let options: Vec<SelectOption> = extract(&extra, "customfield_3").unwrap().unwrap();
+        let values: Vec<&str> = options.iter().map(|option| option.value.as_str()).collect();
+        assert_eq!(values, ["Linux", "macOS"]);
+
+        let cascading: CascadingSelect = extract(&extra, "customfield_4").unwrap().unwrap();
+        assert_eq!(cascading.parent.value, "Hardware");
+        assert_eq!(
+            cascading.child.map(|child| child.value).as_deref(),
+            Some("Disk")
+        );
+
+        let url: Url = extract(&extra, "customfield_5").unwrap().unwrap();
+        assert_eq!(url.host_str(), Some("example.com"));
+
+        assert_eq!(extract::<String>(&extra, "customfield_6").unwrap(), None);
+        assert_eq!(extract::<String>(&extra, "customfield_7").unwrap(), None);
+    }
+
+    #[test]
+    fn schema_mismatch() {
+        let extra = json!({ "customfield_1": ["a", 2] });
+        let error = extract::<Vec<String>>(&extra, "customfield_1").unwrap_err();
+
+        assert!(matches!(
+            &error,
+            JiraQueryError::CustomFieldType { field, .. } if field == "customfield_1"
+        ));
+        assert_eq!(
+            error.to_string(),
+            r#"The custom field customfield_1 doesn't contain an array of items, each a string. It contains: ["a",2]"#
+        );
+    }
+}
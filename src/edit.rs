@@ -0,0 +1,250 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module builds the requests that create or edit issues.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::adf::RichText;
+use crate::issue_model::Visibility;
+
+/// The content of a new issue, or the changes to an existing issue.
+///
+/// Jira accepts two kinds of changes:
+///
+/// * `fields` replace the whole value of a field, such as with `summary` or `labels`.
+/// * `update` operations add, remove, or set individual values of a field,
+///   such as with `add` or `remove`. A field can only appear in one of the two.
+///
+/// Fields that the builder doesn't provide a method for, such as custom fields,
+/// accept any JSON value with `field`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IssueEdit {
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    fields: Map<String, Value>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    update: BTreeMap<String, Vec<Value>>,
+}
+
+/// A reference to a user account.
+///
+/// Jira Server identifies users by their user name. Jira Cloud identifies them by their account ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UserRef {
+    Name(String),
+    AccountId(String),
+}
+
+impl UserRef {
+    /// The JSON object that refers to the user in a request.
+    pub(crate) fn to_value(&self) -> Value {
+        match self {
+            Self::Name(name) => json!({ "name": name }),
+            Self::AccountId(account_id) => json!({ "accountId": account_id }),
+        }
+    }
+}
+
+/// The identification of an issue that Jira has just created.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CreatedIssue {
+    pub id: String,
+    pub key: String,
+    #[serde(rename = "self")]
+    pub self_link: String,
+}
+
+/// A list of references to named objects, such as components.
+fn names(names: &[&str]) -> Value {
+    names.iter().map(|name| json!({ "name": name })).collect()
+}
+
+impl IssueEdit {
+    /// An empty request, which changes nothing.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the project, by its key. Required when you create an issue.
+    #[must_use]
+    pub fn project(self, key: &str) -> Self {
+        self.field("project", json!({ "key": key }))
+    }
+
+    /// Set the issue type, by its name, such as `Bug`. Required when you create an issue.
+    #[must_use]
+    pub fn issue_type(self, name: &str) -> Self {
+        self.field("issuetype", json!({ "name": name }))
+    }
+
+    /// Set the summary.
+    #[must_use]
+    pub fn summary(self, summary: &str) -> Self {
+        self.field("summary", Value::from(summary))
+    }
+
+    /// Set the description, in the wiki markup with API v2, or in ADF with API v3.
+    #[must_use]
+    pub fn description(self, description: impl Into<RichText>) -> Self {
+        self.field("description", rich_text(description.into()))
+    }
+
+    /// Replace all labels.
+    #[must_use]
+    pub fn labels(self, labels: &[&str]) -> Self {
+        self.field("labels", Value::from(labels))
+    }
+
+    /// Replace all components, by their names.
+    #[must_use]
+    pub fn components(self, components: &[&str]) -> Self {
+        self.field("components", names(components))
+    }
+
+    /// Replace all affected versions, by their names.
+    #[must_use]
+    pub fn versions(self, versions: &[&str]) -> Self {
+        self.field("versions", names(versions))
+    }
+
+    /// Replace all fix versions, by their names.
+    #[must_use]
+    pub fn fix_versions(self, versions: &[&str]) -> Self {
+        self.field("fixVersions", names(versions))
+    }
+
+    /// Set the priority, by its name, such as `High`.
+    #[must_use]
+    pub fn priority(self, name: &str) -> Self {
+        self.field("priority", json!({ "name": name }))
+    }
+
+    /// Assign the issue to the user, or unassign it with `None`.
+    #[must_use]
+    pub fn assignee(self, user: Option<&UserRef>) -> Self {
+        self.field("assignee", user.map_or(Value::Null, UserRef::to_value))
+    }
+
+    /// Set the parent issue, by its key. Sub-tasks require a parent.
+    #[must_use]
+    pub fn parent(self, key: &str) -> Self {
+        self.field("parent", json!({ "key": key }))
+    }
+
+    /// Set any field, such as `customfield_12345`, to the JSON value.
+    #[must_use]
+    pub fn field(mut self, id: &str, value: Value) -> Self {
+        self.fields.insert(id.to_string(), value);
+        self
+    }
+
+    /// Add the value to a field that contains a list, such as `labels` or `components`.
+    #[must_use]
+    pub fn add(self, field: &str, value: Value) -> Self {
+        self.operation(field, "add", value)
+    }
+
+    /// Remove the value from a field that contains a list.
+    #[must_use]
+    pub fn remove(self, field: &str, value: Value) -> Self {
+        self.operation(field, "remove", value)
+    }
+
+    /// Set the field to the value as an update operation.
+    #[must_use]
+    pub fn set(self, field: &str, value: Value) -> Self {
+        self.operation(field, "set", value)
+    }
+
+    /// Combine the changes of both requests. The other request's fields take precedence,
+    /// and its update operations follow the operations of this request.
+    pub(crate) fn merge(mut self, other: Self) -> Self {
+        self.fields.extend(other.fields);
+        for (field, mut operations) in other.update {
+            self.update
+                .entry(field)
+                .or_default()
+                .append(&mut operations);
+        }
+        self
+    }
+
+    /// Append the operation to the update operations of the field.
+    fn operation(mut self, field: &str, operation: &str, value: Value) -> Self {
+        let mut object = Map::new();
+        object.insert(operation.to_string(), value);
+        self.update
+            .entry(field.to_string())
+            .or_default()
+            .push(Value::Object(object));
+        self
+    }
+}
+
+/// The content of a new or edited comment.
+#[derive(Serialize)]
+pub(crate) struct CommentBody<'a> {
+    pub body: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<&'a Visibility>,
+}
+
+/// The JSON value of a rich text field in a request: a string or an ADF tree.
+pub(crate) fn rich_text(text: RichText) -> Value {
+    // The rich text consists only of strings and maps with string keys,
+    // which always convert to JSON.
+    serde_json::to_value(text).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_and_updates() {
+        let edit = IssueEdit::new()
+            .project("TEST")
+            .issue_type("Bug")
+            .summary("Export fails")
+            .components(&["Exporter"])
+            .assignee(Some(&UserRef::AccountId("5b10ac8d".to_string())))
+            .field("customfield_10016", json!(3))
+            .add("labels", json!("regression"))
+            .remove("labels", json!("triage"));
+
+        assert_eq!(
+            serde_json::to_value(&edit).unwrap(),
+            json!({
+                "fields": {
+                    "project": { "key": "TEST" },
+                    "issuetype": { "name": "Bug" },
+                    "summary": "Export fails",
+                    "components": [{ "name": "Exporter" }],
+                    "assignee": { "accountId": "5b10ac8d" },
+                    "customfield_10016": 3,
+                },
+                "update": {
+                    "labels": [{ "add": "regression" }, { "remove": "triage" }],
+                },
+            })
+        );
+        assert_eq!(serde_json::to_value(IssueEdit::new()).unwrap(), json!({}));
+    }
+}
@@ -14,15 +14,160 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use std::collections::HashMap;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
 use thiserror::Error;
 
+/// The maximum number of characters of a response body that an error keeps for context.
+const BODY_SNIPPET_LENGTH: usize = 500;
+
 /// All errors that might occur in this crate.
 #[derive(Error, Debug)]
 pub enum JiraQueryError {
     #[error("Required issues are missing in the Jira response: {}.", .0.join(", "))]
     MissingIssues(Vec<String>),
+    #[deprecated(
+        note = "no method returns this error any more; requests by key report `MissingIssues`"
+    )]
     #[error("The Jira query returned no issues.")]
     NoIssues,
     #[error("Error in accessing the Jira REST API.")]
     Request(#[from] reqwest::Error),
+    #[error("Jira rejected the credentials, or the request requires logging in.")]
+    Unauthorized,
+    #[error("The account lacks the permission to access the resource: {}", .0.join(" "))]
+    Forbidden(Vec<String>),
+    #[error("The resource doesn't exist or the account can't see it: {}", .0.join(" "))]
+    NotFound(Vec<String>),
+    #[error("Jira rejected the JQL query: {}", .0.join(" "))]
+    InvalidJql(Vec<String>),
+    #[error("Jira responded with the {status} error: {}", format_messages(.error_messages, .errors))]
+    Http {
+        status: StatusCode,
+        error_messages: Vec<String>,
+        errors: HashMap<String, String>,
+    },
+    #[error("The transition {transition} isn't available for {key}. Available transitions: {}", .available.join(", "))]
+    TransitionUnavailable {
+        key: String,
+        transition: String,
+        available: Vec<String>,
+    },
+    #[error("Jira doesn't support the manual estimate adjustment when editing a worklog.")]
+    UnsupportedAdjustment,
+    #[error("The issues depend on each other in a cycle: {}.", .0.join(", "))]
+    DependencyCycle(Vec<String>),
+    #[error("The Jira instance doesn't define a field named {0}.")]
+    UnknownField(String),
+    #[error("The custom field {field} doesn't contain {expected}. It contains: {value}")]
+    CustomFieldType {
+        field: String,
+        expected: String,
+        value: serde_json::Value,
+    },
+    #[error("Failed to write the downloaded content.")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to deserialize the Jira response from {url}. The response begins with: {body_snippet}")]
+    Deserialize {
+        url: String,
+        body_snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// The error payload that Jira attaches to unsuccessful responses.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ErrorCollection {
+    #[serde(rename = "errorMessages")]
+    error_messages: Vec<String>,
+    errors: HashMap<String, String>,
+}
+
+impl JiraQueryError {
+    /// Convert an unsuccessful Jira response to the matching error.
+    /// If the body isn't the standard Jira error payload, the error carries no messages.
+    pub(crate) fn from_response(status: StatusCode, body: &str) -> Self {
+        let ErrorCollection {
+            error_messages,
+            errors,
+        } = serde_json::from_str(body).unwrap_or_default();
+
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthorized,
+            StatusCode::FORBIDDEN => Self::Forbidden(error_messages),
+            StatusCode::NOT_FOUND => Self::NotFound(error_messages),
+            _ => Self::Http {
+                status,
+                error_messages,
+                errors,
+            },
+        }
+    }
+
+    /// Report a response body that doesn't match the expected JSON structure.
+    pub(crate) fn deserialize(url: &str, body: &str, source: serde_json::Error) -> Self {
+        Self::Deserialize {
+            url: url.to_string(),
+            body_snippet: body.chars().take(BODY_SNIPPET_LENGTH).collect(),
+            source,
+        }
+    }
+
+    /// Jira reports a malformed JQL query as a generic Bad Request error.
+    /// In the context of a search, reinterpret it as an invalid query.
+    pub(crate) fn in_jql_context(self) -> Self {
+        match self {
+            Self::Http {
+                status: StatusCode::BAD_REQUEST,
+                mut error_messages,
+                errors,
+            } => {
+                error_messages.extend(errors.into_values());
+                Self::InvalidJql(error_messages)
+            }
+            other => other,
+        }
+    }
+}
+
+/// List all messages from the Jira error payload on a single line.
+fn format_messages(error_messages: &[String], errors: &HashMap<String, String>) -> String {
+    let mut messages: Vec<String> = error_messages.to_vec();
+    messages.extend(
+        errors
+            .iter()
+            .map(|(field, message)| format!("{field}: {message}")),
+    );
+
+    messages.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_error_payload() {
+        let body = r#"{"errorMessages":["Field 'foo' does not exist."],"errors":{}}"#;
+        let error = JiraQueryError::from_response(StatusCode::BAD_REQUEST, body).in_jql_context();
+
+        assert!(
+            matches!(error, JiraQueryError::InvalidJql(messages) if messages == ["Field 'foo' does not exist."])
+        );
+    }
+
+    #[test]
+    fn tolerate_non_json_error() {
+        let error = JiraQueryError::from_response(StatusCode::BAD_GATEWAY, "<html>Proxy</html>");
+
+        assert!(matches!(
+            error,
+            JiraQueryError::Http { status: StatusCode::BAD_GATEWAY, error_messages, errors }
+                if error_messages.is_empty() && errors.is_empty()
+        ));
+    }
 }
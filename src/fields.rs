@@ -0,0 +1,200 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module describes the fields that a Jira instance defines, including custom fields,
+// and translates between their display names and their IDs.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The metadata of a single field that the Jira instance defines.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FieldDefinition {
+    /// The ID of the field, such as `summary` or `customfield_12345`.
+    pub id: String,
+    /// The display name of the field, such as `Story Points`.
+    pub name: String,
+    /// Whether the field is a custom field.
+    pub custom: bool,
+    /// The data type of the field. Some system fields have no schema.
+    pub schema: Option<FieldSchema>,
+    /// The names that refer to the field in JQL queries.
+    #[serde(rename = "clauseNames", default)]
+    pub clause_names: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The data type of a field.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FieldSchema {
+    /// The JSON type of the field value, such as `number`, `string`, or `array`.
+    #[serde(rename = "type")]
+    pub field_type: String,
+    /// The type of the array items, if the field is an array.
+    pub items: Option<String>,
+    /// The identifier of a system field.
+    pub system: Option<String>,
+    /// The plugin type of a custom field, such as
+    /// `com.atlassian.jira.plugin.system.customfieldtypes:float`.
+    pub custom: Option<String>,
+    /// The numeric ID of a custom field.
+    #[serde(rename = "customId")]
+    pub custom_id: Option<u64>,
+}
+
+impl FieldDefinition {
+    /// The name that refers to this field in a JQL query.
+    ///
+    /// Custom fields use the unambiguous `cf[12345]` form, because several custom fields
+    /// might share a name. System fields use their first clause name, or their ID.
+    #[must_use]
+    pub fn jql_name(&self) -> String {
+        if let Some(custom_id) = self.schema.as_ref().and_then(|schema| schema.custom_id) {
+            format!("cf[{custom_id}]")
+        } else {
+            self.clause_names
+                .first()
+                .cloned()
+                .unwrap_or_else(|| self.id.clone())
+        }
+    }
+}
+
+/// All fields that a Jira instance defines, indexed by their IDs and names.
+///
+/// Download the fields once with `JiraInstance::field_registry` and reuse the registry
+/// to look up custom fields in issues, such as with `CustomFields::custom_field_by_name`.
+#[derive(Clone, Debug, Default)]
+pub struct FieldRegistry {
+    fields: Vec<FieldDefinition>,
+    by_id: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl FieldRegistry {
+    /// Index the field definitions.
+    ///
+    /// Names match regardless of case, like in JQL. If several fields share a name,
+    /// the name refers to the first of them. Use the ID to access the others.
+    #[must_use]
+    pub fn new(fields: Vec<FieldDefinition>) -> Self {
+        let mut by_id = HashMap::new();
+        let mut by_name = HashMap::new();
+
+        for (index, field) in fields.iter().enumerate() {
+            by_id.insert(field.id.clone(), index);
+            by_name.entry(field.name.to_lowercase()).or_insert(index);
+        }
+
+        Self {
+            fields,
+            by_id,
+            by_name,
+        }
+    }
+
+    /// All fields in the registry.
+    #[must_use]
+    pub fn fields(&self) -> &[FieldDefinition] {
+        &self.fields
+    }
+
+    /// The field with the ID, such as `customfield_12345`.
+    #[must_use]
+    pub fn by_id(&self, id: &str) -> Option<&FieldDefinition> {
+        self.by_id.get(id).map(|&index| &self.fields[index])
+    }
+
+    /// The field with the display name, such as `Story Points`.
+    #[must_use]
+    pub fn by_name(&self, name: &str) -> Option<&FieldDefinition> {
+        self.by_name
+            .get(&name.to_lowercase())
+            .map(|&index| &self.fields[index])
+    }
+
+    /// The first custom field of the type, such as `com.pyxis.greenhopper.jira:gh-sprint`.
+    #[must_use]
+    pub fn by_custom_type(&self, custom_type: &str) -> Option<&FieldDefinition> {
+        self.fields.iter().find(|field| {
+            field
+                .schema
+                .as_ref()
+                .and_then(|schema| schema.custom.as_deref())
+                == Some(custom_type)
+        })
+    }
+
+    /// The ID of the field with the display name.
+    #[must_use]
+    pub fn id_of(&self, name: &str) -> Option<&str> {
+        self.by_name(name).map(|field| field.id.as_str())
+    }
+
+    /// The name that refers to the field with the display name in a JQL query.
+    /// For example, `Story Points` might translate to `cf[10016]`.
+    #[must_use]
+    pub fn jql_name(&self, name: &str) -> Option<String> {
+        self.by_name(name).map(FieldDefinition::jql_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> FieldRegistry {
+        let fields = serde_json::json!([
+            {
+                "id": "summary",
+                "name": "Summary",
+                "custom": false,
+                "clauseNames": ["summary"],
+                "schema": { "type": "string", "system": "summary" }
+            },
+            {
+                "id": "customfield_10016",
+                "name": "Story Points",
+                "custom": true,
+                "clauseNames": ["cf[10016]", "Story Points"],
+                "schema": {
+                    "type": "number",
+                    "custom": "com.atlassian.jira.plugin.system.customfieldtypes:float",
+                    "customId": 10016
+                }
+            }
+        ]);
+
+        FieldRegistry::new(serde_json::from_value(fields).unwrap())
+    }
+
+    #[test]
+    fn look_up_names() {
+        let registry = registry();
+
+        assert_eq!(registry.id_of("story points"), Some("customfield_10016"));
+        assert_eq!(
+            registry.jql_name("Story Points").as_deref(),
+            Some("cf[10016]")
+        );
+        assert_eq!(registry.jql_name("Summary").as_deref(), Some("summary"));
+        assert!(registry.by_id("summary").is_some());
+        assert!(registry.by_name("Target Release").is_none());
+    }
+}
@@ -0,0 +1,597 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module represents the relations between issues, such as links, subtasks,
+// and epics, as a directed graph that `JiraInstance::issue_graph` assembles.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::errors::JiraQueryError;
+use crate::issue_model::Issue;
+
+/// The custom field type that holds the epic of an issue on Jira Server.
+pub(crate) const EPIC_LINK_TYPE: &str = "com.pyxis.greenhopper.jira:gh-epic-link";
+
+/// The direction of issue links that the graph follows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkDirection {
+    /// Follow links in their outward direction, such as from an issue to the issues it blocks.
+    Outward,
+    /// Follow links in their inward direction, such as from an issue to the issues that block it.
+    Inward,
+    Both,
+}
+
+/// The relations that `JiraInstance::issue_graph` follows, and how far.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphOptions {
+    depth: u32,
+    // `None` follows all links in both directions.
+    link_types: Option<Vec<(String, LinkDirection)>>,
+    subtasks: bool,
+    parents: bool,
+    epics: bool,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphOptions {
+    /// Follow all links, subtasks, parents, and epics up to one step from the initial issues.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            depth: 1,
+            link_types: None,
+            subtasks: true,
+            parents: true,
+            epics: true,
+        }
+    }
+
+    /// Follow the relations up to the number of steps from the initial issues.
+    /// With zero, the graph only contains the initial issues.
+    #[must_use]
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Follow links of the type, by its name, such as `Blocks`, in the direction.
+    /// Once you select a link type, the graph ignores the link types that you don't select.
+    #[must_use]
+    pub fn link_type(mut self, name: &str, direction: LinkDirection) -> Self {
+        self.link_types
+            .get_or_insert_with(Vec::new)
+            .push((name.to_string(), direction));
+        self
+    }
+
+    /// Ignore all issue links.
+    #[must_use]
+    pub fn without_links(mut self) -> Self {
+        self.link_types = Some(Vec::new());
+        self
+    }
+
+    /// Whether to follow issues to their subtasks.
+    #[must_use]
+    pub fn subtasks(mut self, subtasks: bool) -> Self {
+        self.subtasks = subtasks;
+        self
+    }
+
+    /// Whether to follow subtasks to their parent issues.
+    #[must_use]
+    pub fn parents(mut self, parents: bool) -> Self {
+        self.parents = parents;
+        self
+    }
+
+    /// Whether to follow issues to their epics, and epics to their issues.
+    #[must_use]
+    pub fn epics(mut self, epics: bool) -> Self {
+        self.epics = epics;
+        self
+    }
+
+    pub(crate) fn max_depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub(crate) fn follows_epics(&self) -> bool {
+        self.epics
+    }
+
+    /// Whether the options select the link type in the direction.
+    fn follows_link(&self, name: &str, direction: LinkDirection) -> bool {
+        self.link_types.as_ref().map_or(true, |link_types| {
+            link_types.iter().any(|(selected, selected_direction)| {
+                selected.eq_ignore_ascii_case(name)
+                    && (*selected_direction == LinkDirection::Both
+                        || *selected_direction == direction)
+            })
+        })
+    }
+}
+
+/// The kind of relation between two issues.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Relation {
+    /// An issue link, by the name of its type and its outward description, such as `blocks`.
+    Link { name: String, outward: String },
+    /// A parent issue and its subtask.
+    Parent,
+    /// An epic and an issue in the epic.
+    Epic,
+}
+
+impl Relation {
+    /// A description of the relation that reads from the source issue to the target issue.
+    #[must_use]
+    pub fn description(&self) -> &str {
+        match self {
+            Self::Link { outward, .. } => outward,
+            Self::Parent => "is parent of",
+            Self::Epic => "is epic of",
+        }
+    }
+}
+
+/// A directed relation from one issue to another. Links point in their outward direction,
+/// parents point to their subtasks, and epics point to their issues.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub relation: Relation,
+}
+
+/// The issues around the initial issues and the relations between them.
+#[derive(Clone, Debug)]
+pub struct IssueGraph {
+    issues: BTreeMap<String, Issue>,
+    edges: BTreeSet<Edge>,
+    missing: Vec<String>,
+}
+
+impl IssueGraph {
+    /// Assemble the graph. Keep only the edges between the issues in the graph.
+    pub(crate) fn new(
+        issues: BTreeMap<String, Issue>,
+        edges: impl IntoIterator<Item = Edge>,
+        missing: Vec<String>,
+    ) -> Self {
+        let edges = edges
+            .into_iter()
+            .filter(|edge| issues.contains_key(&edge.from) && issues.contains_key(&edge.to))
+            .collect();
+
+        Self {
+            issues,
+            edges,
+            missing,
+        }
+    }
+
+    /// The issue with the key, if the graph contains it.
+    #[must_use]
+    pub fn issue(&self, key: &str) -> Option<&Issue> {
+        self.issues.get(key)
+    }
+
+    /// All issues in the graph, ordered by their keys.
+    pub fn issues(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.values()
+    }
+
+    /// The number of issues in the graph.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    /// Whether the graph contains no issues.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The keys of related issues that Jira didn't return, such as because
+    /// the account can't see them. The graph leaves them out.
+    #[must_use]
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    /// All relations in the graph.
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter()
+    }
+
+    /// The relations that start at the issue.
+    pub fn edges_from<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Edge> {
+        self.edges.iter().filter(move |edge| edge.from == key)
+    }
+
+    /// The relations that end at the issue.
+    pub fn edges_to<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Edge> {
+        self.edges.iter().filter(move |edge| edge.to == key)
+    }
+
+    /// The keys of the issues, and the successors of each issue by its position in the keys.
+    fn adjacency(&self) -> (Vec<&str>, Vec<Vec<usize>>) {
+        let keys: Vec<&str> = self.issues.keys().map(String::as_str).collect();
+        let positions: BTreeMap<&str, usize> = keys
+            .iter()
+            .enumerate()
+            .map(|(position, key)| (*key, position))
+            .collect();
+
+        let mut successors = vec![Vec::new(); keys.len()];
+        for edge in &self.edges {
+            let from = positions[edge.from.as_str()];
+            let to = positions[edge.to.as_str()];
+            if !successors[from].contains(&to) {
+                successors[from].push(to);
+            }
+        }
+
+        (keys, successors)
+    }
+
+    /// The groups of issues that depend on each other in a cycle, each ordered by the keys.
+    ///
+    /// The search uses the iterative form of Tarjan's algorithm for strongly connected
+    /// components, so that long chains of issues don't exhaust the stack.
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        let (keys, successors) = self.adjacency();
+        let count = keys.len();
+
+        let mut index: Vec<Option<usize>> = vec![None; count];
+        let mut low = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        let mut cycles = Vec::new();
+
+        for root in 0..count {
+            if index[root].is_some() {
+                continue;
+            }
+
+            // Each frame holds a node and the position of its next successor to visit.
+            let mut frames = vec![(root, 0)];
+            index[root] = Some(next_index);
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&(node, position)) = frames.last() {
+                if let Some(&successor) = successors[node].get(position) {
+                    if let Some(frame) = frames.last_mut() {
+                        frame.1 += 1;
+                    }
+                    match index[successor] {
+                        None => {
+                            index[successor] = Some(next_index);
+                            low[successor] = next_index;
+                            next_index += 1;
+                            stack.push(successor);
+                            on_stack[successor] = true;
+                            frames.push((successor, 0));
+                        }
+                        Some(successor_index) if on_stack[successor] => {
+                            low[node] = low[node].min(successor_index);
+                        }
+                        Some(_) => {}
+                    }
+                } else {
+                    frames.pop();
+                    if let Some(&(parent, _)) = frames.last() {
+                        low[parent] = low[parent].min(low[node]);
+                    }
+
+                    if Some(low[node]) == index[node] {
+                        let mut component = Vec::new();
+                        while let Some(member) = stack.pop() {
+                            on_stack[member] = false;
+                            component.push(member);
+                            if member == node {
+                                break;
+                            }
+                        }
+
+                        if component.len() > 1 || successors[node].contains(&node) {
+                            let mut component: Vec<&str> =
+                                component.into_iter().map(|member| keys[member]).collect();
+                            component.sort_unstable();
+                            cycles.push(component);
+                        }
+                    }
+                }
+            }
+        }
+
+        cycles.sort_unstable();
+        cycles
+    }
+
+    /// The keys of all issues, ordered so that each issue precedes the issues
+    /// that its relations point to. For example, a blocking issue precedes the issues
+    /// that it blocks. Issues without an order between them follow their keys.
+    ///
+    /// # Errors
+    ///
+    /// If some issues depend on each other in a cycle, no such order exists,
+    /// and the method fails with `JiraQueryError::DependencyCycle`.
+    pub fn topological_order(&self) -> Result<Vec<&str>, JiraQueryError> {
+        let (keys, successors) = self.adjacency();
+
+        let mut predecessors = vec![0_usize; keys.len()];
+        for targets in &successors {
+            for &target in targets {
+                predecessors[target] += 1;
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..keys.len())
+            .filter(|&node| predecessors[node] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(keys.len());
+
+        while let Some(node) = ready.pop_first() {
+            order.push(keys[node]);
+            for &target in &successors[node] {
+                predecessors[target] -= 1;
+                if predecessors[target] == 0 {
+                    ready.insert(target);
+                }
+            }
+        }
+
+        if order.len() == keys.len() {
+            Ok(order)
+        } else {
+            let cycle = self.cycles().into_iter().next().unwrap_or_default();
+            Err(JiraQueryError::DependencyCycle(
+                cycle.into_iter().map(String::from).collect(),
+            ))
+        }
+    }
+
+    /// Render the graph in the DOT language of Graphviz.
+    ///
+    /// Each node shows the key and the summary of an issue. Links appear as solid arrows
+    /// labeled with their outward description. Parents and epics appear as dashed arrows.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph issues {".to_string()];
+
+        for issue in self.issues.values() {
+            lines.push(format!(
+                "    \"{}\" [label=\"{}\\n{}\"];",
+                escape(&issue.key),
+                escape(&issue.key),
+                escape(&issue.fields.summary)
+            ));
+        }
+
+        for edge in &self.edges {
+            let style = match edge.relation {
+                Relation::Link { .. } => "",
+                Relation::Parent | Relation::Epic => ", style=dashed",
+            };
+            lines.push(format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+                escape(&edge.from),
+                escape(&edge.to),
+                escape(edge.relation.description())
+            ));
+        }
+
+        lines.push("}".to_string());
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+/// Escape the text for a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The relations that the options select among the ones that the issue lists,
+/// whether they start or end at the issue.
+///
+/// On Jira Server, the `epic_link` custom field refers to the epic of the issue.
+/// Jira Cloud refers to epics as parents instead.
+pub(crate) fn relations(
+    issue: &Issue,
+    options: &GraphOptions,
+    epic_link: Option<&str>,
+) -> Vec<Edge> {
+    let key = &issue.key;
+    let fields = &issue.fields;
+    let mut edges = Vec::new();
+
+    for link in &fields.issuelinks {
+        let relation = Relation::Link {
+            name: link.link_type.name.clone(),
+            outward: link.link_type.outward.clone(),
+        };
+
+        if let Some(outward) = &link.outward_issue {
+            if options.follows_link(&link.link_type.name, LinkDirection::Outward) {
+                edges.push(Edge {
+                    from: key.clone(),
+                    to: outward.key.clone(),
+                    relation: relation.clone(),
+                });
+            }
+        }
+        if let Some(inward) = &link.inward_issue {
+            if options.follows_link(&link.link_type.name, LinkDirection::Inward) {
+                edges.push(Edge {
+                    from: inward.key.clone(),
+                    to: key.clone(),
+                    relation,
+                });
+            }
+        }
+    }
+
+    if options.subtasks {
+        for subtask in &fields.subtasks {
+            edges.push(Edge {
+                from: key.clone(),
+                to: subtask.key.clone(),
+                relation: Relation::Parent,
+            });
+        }
+    }
+
+    if let Some(parent) = &fields.parent {
+        let relation = if is_epic_type(&parent.fields.issuetype.name) {
+            Relation::Epic
+        } else {
+            Relation::Parent
+        };
+        let follows = match relation {
+            Relation::Epic => options.epics,
+            _ => options.parents,
+        };
+        if follows {
+            edges.push(Edge {
+                from: parent.key.clone(),
+                to: key.clone(),
+                relation,
+            });
+        }
+    }
+
+    if options.epics {
+        let epic = epic_link.and_then(|field| fields.extra.get(field)?.as_str());
+        if let Some(epic) = epic {
+            edges.push(Edge {
+                from: epic.to_string(),
+                to: key.clone(),
+                relation: Relation::Epic,
+            });
+        }
+    }
+
+    edges
+}
+
+/// Whether the issue is an epic, whose issues the graph can follow.
+pub(crate) fn is_epic(issue: &Issue) -> bool {
+    is_epic_type(&issue.fields.issuetype.name)
+}
+
+fn is_epic_type(name: &str) -> bool {
+    name.eq_ignore_ascii_case("Epic")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(key: &str, summary: &str) -> Issue {
+        let mut issue: Issue =
+            serde_json::from_str(include_str!("../tests/fixtures/issue.json")).unwrap();
+        issue.key = key.to_string();
+        issue.fields.summary = summary.to_string();
+        issue
+    }
+
+    fn blocks(from: &str, to: &str) -> Edge {
+        Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: Relation::Link {
+                name: "Blocks".to_string(),
+                outward: "blocks".to_string(),
+            },
+        }
+    }
+
+    fn graph(keys: &[&str], edges: Vec<Edge>) -> IssueGraph {
+        let issues = keys
+            .iter()
+            .map(|&key| (key.to_string(), issue(key, "Fix \"export\"")))
+            .collect();
+        IssueGraph::new(issues, edges, Vec::new())
+    }
+
+    #[test]
+    fn order_and_cycles() {
+        let graph = graph(
+            &["A-1", "A-2", "A-3", "A-4"],
+            vec![
+                blocks("A-3", "A-1"),
+                blocks("A-1", "A-2"),
+                blocks("A-4", "A-9"),
+            ],
+        );
+
+        assert_eq!(graph.edges().count(), 2);
+        assert!(graph.cycles().is_empty());
+        assert_eq!(
+            graph.topological_order().unwrap(),
+            ["A-3", "A-1", "A-2", "A-4"]
+        );
+
+        let cyclic = IssueGraph::new(
+            graph.issues.clone(),
+            vec![
+                blocks("A-1", "A-2"),
+                blocks("A-2", "A-3"),
+                blocks("A-3", "A-1"),
+                blocks("A-4", "A-4"),
+            ],
+            Vec::new(),
+        );
+
+        assert_eq!(cyclic.cycles(), [vec!["A-1", "A-2", "A-3"], vec!["A-4"]]);
+        assert!(matches!(
+            cyclic.topological_order(),
+            Err(JiraQueryError::DependencyCycle(keys)) if keys == ["A-1", "A-2", "A-3"]
+        ));
+    }
+
+    #[test]
+    fn dot_export() {
+        let mut parent = blocks("A-1", "A-2");
+        parent.relation = Relation::Parent;
+        let graph = graph(&["A-1", "A-2"], vec![parent]);
+
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph issues {
+    "A-1" [label="A-1\nFix \"export\""];
+    "A-2" [label="A-2\nFix \"export\""];
+    "A-1" -> "A-2" [label="is parent of", style=dashed];
+}
+"#
+        );
+    }
+}
@@ -14,22 +14,123 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use std::fmt;
+
 use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
 /// This module replicates the fields in a Jira issue as strongly typed structs.
 /// Any extra fields that come from a custom Jira configuration are captured
 /// in the `extra` hash map in the parent struct.
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::adf::RichText;
+use crate::custom_fields::CustomFields;
+use crate::errors::JiraQueryError;
+use crate::fields::FieldRegistry;
+
 /// The response from Jira to a JQL query,
 /// which includes the list of requested issues and additional metadata.
+///
+/// With pagination, the response represents a single page of the results.
+/// Jira Cloud paginates with a token rather than a position, and doesn't report
+/// the total number of issues. On Cloud, the crate counts `start_at` itself.
 #[derive(Clone, Debug, Deserialize)]
-pub struct JqlResults {
-    pub issues: Vec<Issue>,
+pub struct JqlResults<T = Issue> {
+    /// The index of the first issue on this page within all matching issues.
+    #[serde(rename = "startAt", default)]
+    pub start_at: u32,
+    /// The page size that the instance applied, which might be lower than the requested size.
+    #[serde(rename = "maxResults", default)]
+    pub max_results: u32,
+    /// The number of all issues that match the query, if Jira reports it.
+    pub total: Option<u32>,
+    /// The token that requests the next page on Jira Cloud.
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+    /// Whether this is the last page on Jira Cloud.
+    #[serde(rename = "isLast")]
+    pub is_last: Option<bool>,
+    pub issues: Vec<T>,
     #[serde(flatten)]
     pub extra: Value,
 }
 
+impl<T> JqlResults<T> {
+    /// The number of issues on this page and all the previous pages.
+    #[must_use]
+    pub fn fetched(&self) -> u32 {
+        // The page length can't exceed `maxResults`, which is a `u32`.
+        self.start_at
+            .saturating_add(u32::try_from(self.issues.len()).unwrap_or(u32::MAX))
+    }
+
+    /// Whether this is the last page of the results.
+    #[must_use]
+    pub fn is_last_page(&self) -> bool {
+        if self.issues.is_empty() || self.is_last == Some(true) {
+            return true;
+        }
+
+        match self.total {
+            Some(total) => self.fetched() >= total,
+            // Without the total, only the token signals that more pages follow.
+            None => self.next_page_token.is_none(),
+        }
+    }
+}
+
+/// The response from the Jira Cloud endpoint that fetches several issues by their keys.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct BulkFetchResults<T> {
+    pub issues: Vec<T>,
+}
+
+/// A type that Jira issues in the responses deserialize into.
+///
+/// The crate implements this trait for `Issue`, which requires the complete set of fields,
+/// and for `PartialIssue`, which tolerates a reduced set of fields.
+pub trait IssueRepresentation: DeserializeOwned + fmt::Debug + Send + 'static {
+    /// The key of the issue, such as `PROJECT-123`.
+    fn key(&self) -> &str;
+}
+
+impl IssueRepresentation for Issue {
+    fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl IssueRepresentation for PartialIssue {
+    fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl CustomFields for Issue {
+    fn extra_fields(&self) -> &Value {
+        &self.fields.extra
+    }
+}
+
+impl CustomFields for PartialIssue {
+    fn extra_fields(&self) -> &Value {
+        &self.fields.extra
+    }
+}
+
+impl CustomFields for Fields {
+    fn extra_fields(&self) -> &Value {
+        &self.extra
+    }
+}
+
+impl CustomFields for PartialFields {
+    fn extra_fields(&self) -> &Value {
+        &self.extra
+    }
+}
+
 /// A single Jira issue with all its fields.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Issue {
@@ -37,65 +138,211 @@ pub struct Issue {
     pub key: String,
     pub expand: String,
     pub fields: Fields,
+    /// The history of changes, if you request it with `Expand::Changelog`.
+    pub changelog: Option<Changelog>,
     #[serde(rename = "self")]
     pub self_link: String,
     #[serde(flatten)]
     pub extra: Value,
 }
 
-/// A container for most fields of a Jira issue.
+/// Define the fields of a complete issue and the matching fields of a partial issue
+/// from a single list, so that the two structs can't drift apart.
+///
+/// Fields marked `required` must be present in a complete issue,
+/// and become optional in a partial issue. Fields marked `pub` have the same type in both.
+/// A partial issue fills in any missing field with its default value.
+macro_rules! issue_fields {
+    (
+        $(#[$complete_meta:meta])*
+        pub struct $complete:ident;
+        $(#[$partial_meta:meta])*
+        pub struct $partial:ident;
+        {
+            $(
+                $(#[$field_meta:meta])*
+                $kind:ident $field:ident: $ty:ty,
+            )*
+        }
+    ) => {
+        $(#[$complete_meta])*
+        pub struct $complete {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+            #[serde(flatten)]
+            pub extra: Value,
+        }
+
+        $(#[$partial_meta])*
+        #[serde(default)]
+        pub struct $partial {
+            $(
+                $(#[$field_meta])*
+                pub $field: issue_fields!(@partial $kind $ty),
+            )*
+            #[serde(flatten)]
+            pub extra: Value,
+        }
+    };
+    (@partial pub $ty:ty) => { $ty };
+    (@partial required $ty:ty) => { Option<$ty> };
+}
+
+issue_fields! {
+    /// A container for most fields of a Jira issue.
+    #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct Fields;
+
+    /// The fields of a Jira issue, any of which might be missing from the response.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+    pub struct PartialFields;
+
+    {
+        #[serde(rename = "lastViewed")]
+        pub last_viewed: Option<DateTime<Utc>>,
+        pub labels: Vec<String>,
+        pub assignee: Option<User>,
+        pub description: Option<RichText>,
+        pub duedate: Option<NaiveDate>,
+        // Both `versions` and `fixVersions` are optional fields and they might
+        // either be missing or set to an empty list.
+        // I'm consolidating both cases as an empty list, because I don't believe
+        // that there's a meaningful semantic difference between them here.
+        #[serde(default)]
+        pub versions: Vec<Version>,
+        #[serde(default)]
+        #[serde(rename = "fixVersions")]
+        pub fix_versions: Vec<Version>,
+        required reporter: User,
+        required status: Status,
+        required created: DateTime<Utc>,
+        required updated: DateTime<Utc>,
+        required issuetype: IssueType,
+        pub timeestimate: Option<i32>,
+        pub aggregatetimeestimate: Option<i32>,
+        pub timeoriginalestimate: Option<i32>,
+        pub timespent: Option<i32>,
+        pub aggregatetimespent: Option<i32>,
+        pub aggregatetimeoriginalestimate: Option<i32>,
+        pub progress: Option<Progress>,
+        pub aggregateprogress: Option<Progress>,
+        required workratio: i64,
+        required summary: String,
+        required creator: User,
+        required project: Project,
+        pub priority: Option<Priority>,
+        pub components: Vec<Component>,
+        required watches: Watches,
+        pub archiveddate: Option<DateTime<Utc>>,
+        pub archivedby: Option<DateTime<Utc>>,
+        pub resolution: Option<Resolution>,
+        pub resolutiondate: Option<DateTime<Utc>>,
+        pub comment: Option<Comments>,
+        pub issuelinks: Vec<IssueLink>,
+        required votes: Votes,
+        pub parent: Option<CondensedIssue>,
+        pub subtasks: Vec<CondensedIssue>,
+        pub environment: Option<RichText>,
+        pub security: Option<Security>,
+        #[serde(default)]
+        pub attachment: Vec<Attachment>,
+    }
+}
+
+/// A Jira issue with a reduced set of fields.
+///
+/// Use this type when you request only some fields with `RequestOptions::fields`.
+/// Unlike `Issue`, it deserializes regardless of which fields Jira returns.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub struct Fields {
-    #[serde(rename = "lastViewed")]
-    pub last_viewed: Option<DateTime<Utc>>,
-    pub labels: Vec<String>,
-    pub assignee: Option<User>,
-    pub description: Option<String>,
-    pub duedate: Option<NaiveDate>,
-    // Both `versions` and `fixVersions` are optional fields and they might
-    // either be missing or set to an empty list.
-    // I'm consolidating both cases as an empty list, because I don't believe
-    // that there's a meaningful semantic difference between them here.
-    #[serde(default)]
-    pub versions: Vec<Version>,
+pub struct PartialIssue {
+    pub id: String,
+    pub key: String,
+    pub expand: Option<String>,
     #[serde(default)]
-    #[serde(rename = "fixVersions")]
-    pub fix_versions: Vec<Version>,
-    pub reporter: User,
-    pub status: Status,
+    pub fields: PartialFields,
+    /// The history of changes, if you request it with `Expand::Changelog`.
+    pub changelog: Option<Changelog>,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The history of changes to a Jira issue, embedded in the issue.
+///
+/// Jira might embed only the most recent histories. If `total` exceeds the number
+/// of `histories`, use `JiraInstance::changelog` to access all of them.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Changelog {
+    #[serde(rename = "startAt")]
+    pub start_at: u32,
+    #[serde(rename = "maxResults")]
+    pub max_results: u32,
+    pub total: u32,
+    pub histories: Vec<ChangeHistory>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// A single change to a Jira issue, which might modify several fields at once.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangeHistory {
+    pub id: String,
+    /// The user who made the change. Missing for changes made by Jira itself.
+    pub author: Option<User>,
     pub created: DateTime<Utc>,
-    pub updated: DateTime<Utc>,
-    pub issuetype: IssueType,
-    pub timeestimate: Option<i32>,
-    pub aggregatetimeestimate: Option<i32>,
-    pub timeoriginalestimate: Option<i32>,
-    pub timespent: Option<i32>,
-    pub aggregatetimespent: Option<i32>,
-    pub aggregatetimeoriginalestimate: Option<i32>,
-    pub progress: Option<Progress>,
-    pub aggregateprogress: Option<Progress>,
-    pub workratio: i64,
-    pub summary: String,
-    pub creator: User,
-    pub project: Project,
-    pub priority: Option<Priority>,
-    pub components: Vec<Component>,
-    pub watches: Watches,
-    pub archiveddate: Option<DateTime<Utc>>,
-    pub archivedby: Option<DateTime<Utc>>,
-    pub resolution: Option<Resolution>,
-    pub resolutiondate: Option<DateTime<Utc>>,
-    pub comment: Option<Comments>,
-    pub issuelinks: Vec<IssueLink>,
-    pub votes: Votes,
-    pub parent: Option<CondensedIssue>,
-    pub subtasks: Vec<CondensedIssue>,
-    pub environment: Option<String>,
-    pub security: Option<Security>,
+    pub items: Vec<ChangeItem>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The modification of a single field within a change to a Jira issue.
+///
+/// The `from` and `to` values are internal IDs, such as a status ID,
+/// while `from_string` and `to_string` are their display forms, such as a status name.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangeItem {
+    pub field: String,
+    pub fieldtype: String,
+    #[serde(rename = "fieldId")]
+    pub field_id: Option<String>,
+    pub from: Option<String>,
+    #[serde(rename = "fromString")]
+    pub from_string: Option<String>,
+    pub to: Option<String>,
+    #[serde(rename = "toString")]
+    pub to_string: Option<String>,
     #[serde(flatten)]
     pub extra: Value,
 }
 
+/// A single page of a paginated list, as returned by the newer Jira endpoints.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct PageBean<T> {
+    #[serde(rename = "startAt")]
+    pub start_at: u32,
+    pub total: Option<u32>,
+    #[serde(rename = "isLast")]
+    pub is_last: Option<bool>,
+    /// Some older endpoints name the list after its items, such as `comments`.
+    #[serde(alias = "comments", alias = "worklogs")]
+    pub values: Vec<T>,
+}
+
+impl<T> PageBean<T> {
+    /// Whether this is the last page of the list.
+    /// Not all endpoints report `isLast` and `total`, so use whichever is present.
+    pub(crate) fn is_last_page(&self) -> bool {
+        let fetched = self.start_at as usize + self.values.len();
+
+        self.values.is_empty()
+            || self.is_last == Some(true)
+            || self.total.is_some_and(|total| fetched >= total as usize)
+    }
+}
+
 /// The representation of a Jira user account.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct User {
@@ -271,11 +518,32 @@ pub struct Progress {
     pub extra: Value,
 }
 
+/// A file attached to a Jira issue.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Attachment {
+    pub id: String,
+    pub filename: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub author: Option<User>,
+    pub created: DateTime<Utc>,
+    /// The URL that serves the content of the file.
+    pub content: String,
+    /// The URL of a preview image, if Jira can render one.
+    pub thumbnail: Option<String>,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
 /// A comment below a Jira issue.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Comment {
     pub author: User,
-    pub body: String,
+    pub body: RichText,
     pub created: DateTime<Utc>,
     pub id: String,
     #[serde(rename = "updateAuthor")]
@@ -417,6 +685,28 @@ pub struct Visibility {
     pub extra: Value,
 }
 
+impl Visibility {
+    /// Restrict the visibility to the members of the project role, such as `Developers`.
+    #[must_use]
+    pub fn role(name: &str) -> Self {
+        Self {
+            r#type: "role".to_string(),
+            value: name.to_string(),
+            extra: Value::Null,
+        }
+    }
+
+    /// Restrict the visibility to the members of the group, such as `jira-administrators`.
+    #[must_use]
+    pub fn group(name: &str) -> Self {
+        Self {
+            r#type: "group".to_string(),
+            value: name.to_string(),
+            extra: Value::Null,
+        }
+    }
+}
+
 /// The security level of a Jira issue.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 // TODO: This seems to be a generic container, similar to several other structs.
@@ -432,3 +722,260 @@ pub struct Security {
     #[serde(flatten)]
     pub extra: Value,
 }
+
+/// A sprint on a Scrum board.
+///
+/// Jira represents sprints either as JSON objects, or in the sprint custom field
+/// of older Jira Server versions, as strings in the legacy `GreenHopper` format, such as
+/// `com.atlassian.greenhopper.service.sprint.Sprint@1a2b[id=12,rapidViewId=3,state=CLOSED,...]`.
+/// This type deserializes from both.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "SprintRepresentation")]
+pub struct Sprint {
+    pub id: u64,
+    pub name: String,
+    pub state: SprintState,
+    #[serde(rename = "startDate")]
+    pub start_date: Option<DateTime<Utc>>,
+    #[serde(rename = "endDate")]
+    pub end_date: Option<DateTime<Utc>>,
+    #[serde(rename = "completeDate")]
+    pub complete_date: Option<DateTime<Utc>>,
+    #[serde(rename = "originBoardId")]
+    pub origin_board_id: Option<u64>,
+    pub goal: Option<String>,
+    #[serde(rename = "self")]
+    pub self_link: Option<String>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The state of a sprint.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SprintState {
+    Future,
+    Active,
+    Closed,
+}
+
+impl SprintState {
+    /// The identifier of the state in the Jira API.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Future => "future",
+            Self::Active => "active",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+/// The sprints that Jira might send: a JSON object or a legacy string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SprintRepresentation {
+    Object(SprintObject),
+    Legacy(String),
+}
+
+/// The JSON object of a sprint, with the same structure as `Sprint`.
+#[derive(Deserialize)]
+struct SprintObject {
+    id: u64,
+    name: String,
+    state: SprintState,
+    #[serde(rename = "startDate")]
+    start_date: Option<DateTime<Utc>>,
+    #[serde(rename = "endDate")]
+    end_date: Option<DateTime<Utc>>,
+    #[serde(rename = "completeDate")]
+    complete_date: Option<DateTime<Utc>>,
+    #[serde(rename = "originBoardId")]
+    origin_board_id: Option<u64>,
+    goal: Option<String>,
+    #[serde(rename = "self")]
+    self_link: Option<String>,
+    #[serde(flatten)]
+    extra: Value,
+}
+
+impl From<SprintObject> for Sprint {
+    fn from(object: SprintObject) -> Self {
+        Self {
+            id: object.id,
+            name: object.name,
+            state: object.state,
+            start_date: object.start_date,
+            end_date: object.end_date,
+            complete_date: object.complete_date,
+            origin_board_id: object.origin_board_id,
+            goal: object.goal,
+            self_link: object.self_link,
+            extra: object.extra,
+        }
+    }
+}
+
+impl TryFrom<SprintRepresentation> for Sprint {
+    type Error = String;
+
+    fn try_from(representation: SprintRepresentation) -> Result<Self, Self::Error> {
+        match representation {
+            SprintRepresentation::Object(object) => Ok(object.into()),
+            SprintRepresentation::Legacy(legacy) => {
+                let object = legacy_sprint_object(&legacy)
+                    .ok_or_else(|| format!("Invalid legacy sprint: {legacy}"))?;
+                SprintObject::deserialize(object)
+                    .map(Self::from)
+                    .map_err(|e| format!("Invalid legacy sprint: {legacy}: {e}"))
+            }
+        }
+    }
+}
+
+/// Convert a sprint in the legacy `GreenHopper` format to the equivalent JSON object.
+///
+/// The attributes are separated by commas, but the name and the goal might contain commas too.
+/// Therefore, a new attribute only starts at a comma that precedes an identifier and `=`.
+fn legacy_sprint_object(legacy: &str) -> Option<Value> {
+    let start = legacy.find('[')?;
+    let attributes = legacy[start + 1..].strip_suffix(']')?;
+
+    let mut pairs: Vec<(&str, String)> = Vec::new();
+    for part in attributes.split(',') {
+        let new_attribute = part
+            .split_once('=')
+            .filter(|(key, _)| !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric()));
+        match (new_attribute, pairs.last_mut()) {
+            (Some((key, value)), _) => pairs.push((key, value.to_string())),
+            (None, Some((_, value))) => {
+                value.push(',');
+                value.push_str(part);
+            }
+            (None, None) => return None,
+        }
+    }
+
+    let mut object = serde_json::Map::new();
+    for (key, value) in pairs {
+        if value == "<null>" {
+            continue;
+        }
+        let value = match key {
+            "id" | "rapidViewId" => Value::from(value.parse::<u64>().ok()?),
+            "state" => Value::from(value.to_lowercase()),
+            _ => Value::from(value),
+        };
+        // The legacy format calls the board a rapid view.
+        let key = if key == "rapidViewId" {
+            "originBoardId"
+        } else {
+            key
+        };
+        object.insert(key.to_string(), value);
+    }
+
+    Some(Value::Object(object))
+}
+
+/// The sprints that an issue belongs to, as recorded in the sprint custom field.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IssueSprints {
+    /// The sprint that the issue is planned in and that hasn't closed yet: either active or future.
+    pub current: Option<Sprint>,
+    /// The closed sprints that the issue was part of.
+    pub past: Vec<Sprint>,
+}
+
+impl Issue {
+    /// The current and past sprints of the issue.
+    ///
+    /// The registry identifies the sprint custom field by its type,
+    /// or if the instance doesn't report the type, by the name `Sprint`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::UnknownField` if the instance doesn't define a sprint field,
+    /// and with `JiraQueryError::CustomFieldType` if the field doesn't contain sprints.
+    pub fn sprints(&self, registry: &FieldRegistry) -> Result<IssueSprints, JiraQueryError> {
+        let field = registry
+            .by_custom_type(SPRINT_FIELD_TYPE)
+            .or_else(|| registry.by_name("Sprint"))
+            .ok_or_else(|| JiraQueryError::UnknownField("Sprint".to_string()))?;
+        let sprints: Vec<Sprint> = self.fields.custom_field(&field.id)?.unwrap_or_default();
+
+        let (past, open): (Vec<Sprint>, Vec<Sprint>) = sprints
+            .into_iter()
+            .partition(|sprint| sprint.state == SprintState::Closed);
+        // An issue can only be in a single open sprint. Prefer the active one if Jira reports more.
+        let current = open
+            .iter()
+            .find(|sprint| sprint.state == SprintState::Active)
+            .or_else(|| open.first())
+            .cloned();
+
+        Ok(IssueSprints { current, past })
+    }
+}
+
+/// The type of the sprint custom field that Jira Software defines.
+const SPRINT_FIELD_TYPE: &str = "com.pyxis.greenhopper.jira:gh-sprint";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_fields() {
+        let fields = serde_json::json!({
+            "summary": "Crash on save",
+            "labels": ["crash"],
+            "customfield_10016": 3,
+        });
+        let partial: PartialFields = serde_json::from_value(fields.clone()).unwrap();
+
+        assert_eq!(partial.summary.as_deref(), Some("Crash on save"));
+        assert_eq!(partial.labels, ["crash"]);
+        assert_eq!(partial.reporter, None);
+        assert!(partial.subtasks.is_empty());
+        assert_eq!(partial.extra["customfield_10016"], 3);
+        assert!(serde_json::from_value::<Fields>(fields).is_err());
+    }
+
+    #[test]
+    fn legacy_sprint() {
+        let legacy = "com.atlassian.greenhopper.service.sprint.Sprint@1a2b[id=12,rapidViewId=3,\
+            state=CLOSED,name=Sprint 7, the big one,goal=<null>,\
+            startDate=2022-05-24T10:00:00.000+02:00,endDate=2022-06-07T10:00:00.000+02:00,\
+            completeDate=<null>,sequence=12]";
+        let sprint: Sprint = serde_json::from_value(Value::from(legacy)).unwrap();
+
+        assert_eq!(sprint.id, 12);
+        assert_eq!(sprint.name, "Sprint 7, the big one");
+        assert_eq!(sprint.state, SprintState::Closed);
+        assert_eq!(sprint.origin_board_id, Some(3));
+        assert_eq!(sprint.goal, None);
+        assert_eq!(
+            sprint.start_date.map(|date| date.to_rfc3339()).as_deref(),
+            Some("2022-05-24T08:00:00+00:00")
+        );
+        assert_eq!(sprint.extra["sequence"], "12");
+    }
+
+    #[test]
+    fn sprint_object() {
+        let object = serde_json::json!({
+            "id": 13,
+            "name": "Sprint 8",
+            "state": "active",
+            "boardId": 3,
+            "startDate": "2022-06-07T10:00:00.000Z",
+        });
+        let sprint: Sprint = serde_json::from_value(object).unwrap();
+
+        assert_eq!(sprint.id, 13);
+        assert_eq!(sprint.state, SprintState::Active);
+        assert_eq!(sprint.extra["boardId"], 3);
+    }
+}
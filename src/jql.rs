@@ -0,0 +1,768 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module builds JQL queries from typed clauses, so that field names
+// and values always appear with the correct quoting.
+
+use std::fmt;
+use std::ops::Not;
+
+use chrono::NaiveDate;
+
+use crate::errors::JiraQueryError;
+use crate::fields::FieldRegistry;
+
+// Words that JQL reserves for its own syntax or for future use, as Jira documents them,
+// and the keywords of the history operators. Field names that match them need quotes.
+// The list is sorted for binary search.
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "a", "abort", "access", "add", "after", "alias", "all", "alter", "and", "any", "are", "as",
+    "asc", "at", "audit", "avg", "before", "begin", "between", "boolean", "break", "by", "byte",
+    "catch", "cf", "changed", "char", "character", "check", "checkpoint", "collate", "collation",
+    "column", "commit", "connect", "continue", "count", "create", "current", "date", "decimal",
+    "declare", "decrement", "default", "defaults", "define", "delete", "delimiter", "desc",
+    "difference", "distinct", "divide", "do", "double", "drop", "during", "else", "empty",
+    "encoding", "end", "equals", "escape", "exclusive", "exec", "execute", "exists", "explain",
+    "false", "fetch", "field", "file", "first", "float", "for", "from", "function", "go", "goto",
+    "grant", "greater", "group", "having", "identified", "if", "immediate", "in", "increment",
+    "index", "initial", "inner", "inout", "input", "insert", "int", "integer", "intersect",
+    "intersection", "into", "is", "isempty", "isnull", "join", "last", "left", "less", "like",
+    "limit", "lock", "long", "max", "min", "minus", "mode", "modify", "modulo", "more", "multiply",
+    "next", "noaudit", "not", "notin", "nowait", "null", "number", "object", "of", "on", "option",
+    "or", "order", "outer", "output", "power", "previous", "prior", "privileges", "public",
+    "raise", "raw", "remainder", "rename", "resource", "return", "returns", "revoke", "right",
+    "row", "rowid", "rownum", "rows", "select", "session", "set", "share", "size", "sqrt", "start",
+    "strict", "string", "subtract", "sum", "synonym", "table", "then", "to", "trans",
+    "transaction", "trigger", "true", "uid", "union", "unique", "update", "user", "validate",
+    "values", "view", "was", "when", "whenever", "where", "while", "with",
+];
+
+/// A JQL query that you assemble from clauses, for use with `JiraInstance::search_jql`.
+///
+/// The query quotes all values and the field names that need quotes, so that values with
+/// spaces or reserved words, such as a project named `Release Notes`, can't break the query.
+/// Render the query with `to_string`.
+///
+/// A query without conditions matches all issues, and the combinations follow from that:
+/// `and` with it leaves the other query unchanged, `or` with it matches all issues,
+/// and its negation matches no issues.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Jql {
+    /// The condition, or `None` for a query that matches all issues.
+    clause: Option<Clause>,
+    order_by: Vec<(String, SortOrder)>,
+}
+
+/// A condition of a query. Terms hold a single comparison that's already rendered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Clause {
+    Term(String),
+    And(Vec<Clause>),
+    Or(Vec<Clause>),
+    Not(Box<Clause>),
+    /// A condition that no issue matches, such as membership in an empty list.
+    Nothing,
+}
+
+/// The direction of sorting in the `ORDER BY` part of a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A field in a query, which you compare with values to form a clause.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JqlField {
+    name: String,
+}
+
+/// A value in a query: a string, a number, or a function such as `currentUser()`.
+///
+/// Create values with the `From` conversions and the constructors,
+/// which validate or quote the content so that it can't change the structure of the query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JqlValue(ValueKind);
+
+/// The content of a value, already validated or ready to quote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ValueKind {
+    Text(String),
+    /// A number that comes from an integer or a finite float.
+    Number(String),
+    /// A function with its name and its quoted arguments.
+    Function {
+        name: String,
+        args: Vec<String>,
+    },
+}
+
+/// The conditions of the history operators `WAS` and `CHANGED`,
+/// such as who made the change and when.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JqlHistory {
+    predicates: Vec<String>,
+}
+
+impl Jql {
+    /// A query without conditions, which matches all issues.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a clause with the field, by its JQL name, such as `status` or `cf[10016]`.
+    #[must_use]
+    pub fn field(name: &str) -> JqlField {
+        JqlField {
+            name: field_name(name),
+        }
+    }
+
+    /// Start a clause with the field, by its display name, such as `Story Points`.
+    /// Custom fields translate to their unambiguous `cf[12345]` form.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `JiraQueryError::UnknownField` if the registry doesn't contain the name.
+    pub fn named_field(registry: &FieldRegistry, name: &str) -> Result<JqlField, JiraQueryError> {
+        registry
+            .jql_name(name)
+            .map(|jql_name| Self::field(&jql_name))
+            .ok_or_else(|| JiraQueryError::UnknownField(name.to_string()))
+    }
+
+    /// Match the issues that match both this query and the other query.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        self.combine(other, true)
+    }
+
+    /// Match the issues that match either this query or the other query.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        self.combine(other, false)
+    }
+
+    /// Sort the results by the field, after any previous sorting fields.
+    #[must_use]
+    pub fn order_by(mut self, field: &str, order: SortOrder) -> Self {
+        self.order_by.push((field_name(field), order));
+        self
+    }
+
+    fn term(term: String) -> Self {
+        Self {
+            clause: Some(Clause::Term(term)),
+            order_by: Vec::new(),
+        }
+    }
+
+    /// A query that matches no issues.
+    fn nothing() -> Self {
+        Self {
+            clause: Some(Clause::Nothing),
+            order_by: Vec::new(),
+        }
+    }
+
+    /// Join the conditions with `AND`, or with `OR`, and flatten nested uses of the same operator.
+    /// A query that matches all issues, or no issues, either drops out or decides the result.
+    fn combine(mut self, other: Self, and: bool) -> Self {
+        self.order_by.extend(other.order_by);
+        self.clause = match (self.clause, other.clause) {
+            (None, clause) | (clause, None) if and => clause,
+            (None, _) | (_, None) => None,
+            (Some(Clause::Nothing), _) | (_, Some(Clause::Nothing)) if and => Some(Clause::Nothing),
+            (Some(Clause::Nothing), clause) | (clause, Some(Clause::Nothing)) => clause,
+            (Some(left), Some(right)) => {
+                let mut clauses = Vec::new();
+                for clause in [left, right] {
+                    match clause {
+                        Clause::And(mut nested) if and => clauses.append(&mut nested),
+                        Clause::Or(mut nested) if !and => clauses.append(&mut nested),
+                        clause => clauses.push(clause),
+                    }
+                }
+                Some(if and {
+                    Clause::And(clauses)
+                } else {
+                    Clause::Or(clauses)
+                })
+            }
+        };
+        self
+    }
+}
+
+impl Not for Jql {
+    type Output = Self;
+
+    /// Match the issues that don't match the query.
+    fn not(mut self) -> Self {
+        self.clause = match self.clause {
+            None => Some(Clause::Nothing),
+            Some(Clause::Nothing) => None,
+            Some(clause) => Some(Clause::Not(Box::new(clause))),
+        };
+        self
+    }
+}
+
+impl JqlField {
+    fn compare(self, operator: &str, value: impl fmt::Display) -> Jql {
+        Jql::term(format!("{} {operator} {value}", self.name))
+    }
+
+    /// The field equals the value.
+    #[must_use]
+    pub fn eq(self, value: impl Into<JqlValue>) -> Jql {
+        self.compare("=", value.into())
+    }
+
+    /// The field doesn't equal the value.
+    #[must_use]
+    pub fn ne(self, value: impl Into<JqlValue>) -> Jql {
+        self.compare("!=", value.into())
+    }
+
+    /// The field is greater than the value, such as a later date.
+    #[must_use]
+    pub fn gt(self, value: impl Into<JqlValue>) -> Jql {
+        self.compare(">", value.into())
+    }
+
+    /// The field is greater than or equal to the value.
+    #[must_use]
+    pub fn gte(self, value: impl Into<JqlValue>) -> Jql {
+        self.compare(">=", value.into())
+    }
+
+    /// The field is less than the value, such as an earlier date.
+    #[must_use]
+    pub fn lt(self, value: impl Into<JqlValue>) -> Jql {
+        self.compare("<", value.into())
+    }
+
+    /// The field is less than or equal to the value.
+    #[must_use]
+    pub fn lte(self, value: impl Into<JqlValue>) -> Jql {
+        self.compare("<=", value.into())
+    }
+
+    /// The text field contains the words, such as in a summary.
+    #[must_use]
+    pub fn contains(self, text: &str) -> Jql {
+        self.compare("~", quote(text))
+    }
+
+    /// The text field doesn't contain the words.
+    #[must_use]
+    pub fn not_contains(self, text: &str) -> Jql {
+        self.compare("!~", quote(text))
+    }
+
+    /// The field equals one of the values. With no values, no issues match.
+    #[must_use]
+    pub fn is_in<V: Into<JqlValue>>(self, values: impl IntoIterator<Item = V>) -> Jql {
+        match list(values) {
+            Some(list) => self.compare("IN", list),
+            None => Jql::nothing(),
+        }
+    }
+
+    /// The field equals none of the values. With no values, all issues match.
+    #[must_use]
+    pub fn not_in<V: Into<JqlValue>>(self, values: impl IntoIterator<Item = V>) -> Jql {
+        match list(values) {
+            Some(list) => self.compare("NOT IN", list),
+            None => Jql::new(),
+        }
+    }
+
+    /// The field has no value.
+    #[must_use]
+    pub fn is_empty(self) -> Jql {
+        Jql::term(format!("{} IS EMPTY", self.name))
+    }
+
+    /// The field has a value.
+    #[must_use]
+    pub fn is_not_empty(self) -> Jql {
+        Jql::term(format!("{} IS NOT EMPTY", self.name))
+    }
+
+    /// The field had the value at some point, under the conditions of the history.
+    #[must_use]
+    pub fn was(self, value: impl Into<JqlValue>, history: &JqlHistory) -> Jql {
+        Jql::term(format!("{} WAS {}{history}", self.name, value.into()))
+    }
+
+    /// The field never had the value, under the conditions of the history.
+    #[must_use]
+    pub fn was_not(self, value: impl Into<JqlValue>, history: &JqlHistory) -> Jql {
+        Jql::term(format!("{} WAS NOT {}{history}", self.name, value.into()))
+    }
+
+    /// The field had one of the values at some point, under the conditions of the history.
+    /// With no values, no issues match.
+    #[must_use]
+    pub fn was_in<V: Into<JqlValue>>(
+        self,
+        values: impl IntoIterator<Item = V>,
+        history: &JqlHistory,
+    ) -> Jql {
+        match list(values) {
+            Some(list) => Jql::term(format!("{} WAS IN {list}{history}", self.name)),
+            None => Jql::nothing(),
+        }
+    }
+
+    /// The field changed, under the conditions of the history.
+    #[must_use]
+    pub fn changed(self, history: &JqlHistory) -> Jql {
+        Jql::term(format!("{} CHANGED{history}", self.name))
+    }
+}
+
+/// Render the values as a parenthesized JQL list, or `None` if there are no values,
+/// because JQL rejects an empty list.
+fn list<V: Into<JqlValue>>(values: impl IntoIterator<Item = V>) -> Option<String> {
+    let values: Vec<String> = values
+        .into_iter()
+        .map(|value| value.into().to_string())
+        .collect();
+    (!values.is_empty()).then(|| format!("({})", values.join(", ")))
+}
+
+impl JqlValue {
+    /// A JQL function with string arguments, such as `membersOf("jira-users")`.
+    /// Returns `None` if the name isn't a valid identifier, which consists of letters,
+    /// digits, and underscores and doesn't start with a digit.
+    #[must_use]
+    pub fn function(name: &str, args: &[&str]) -> Option<Self> {
+        let mut chars = name.chars();
+        let is_identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        is_identifier.then(|| Self::builtin(name, args))
+    }
+
+    /// A function that the crate knows to be valid.
+    fn builtin(name: &str, args: &[&str]) -> Self {
+        Self(ValueKind::Function {
+            name: name.to_string(),
+            args: args.iter().map(|arg| quote(arg)).collect(),
+        })
+    }
+
+    /// A decimal number, such as a story point estimate of `1.5`.
+    /// Returns `None` for infinite numbers and `NaN`, which JQL can't express.
+    #[must_use]
+    pub fn decimal(number: f64) -> Option<Self> {
+        number
+            .is_finite()
+            .then(|| Self(ValueKind::Number(number.to_string())))
+    }
+
+    /// The current time.
+    #[must_use]
+    pub fn now() -> Self {
+        Self::builtin("now", &[])
+    }
+
+    /// The user who runs the query.
+    #[must_use]
+    pub fn current_user() -> Self {
+        Self::builtin("currentUser", &[])
+    }
+
+    /// The start of the current day, optionally shifted by an offset such as `-7d`.
+    #[must_use]
+    pub fn start_of_day(offset: Option<&str>) -> Self {
+        Self::builtin("startOfDay", offset.as_slice())
+    }
+
+    /// The end of the current day, optionally shifted by an offset such as `+1d`.
+    #[must_use]
+    pub fn end_of_day(offset: Option<&str>) -> Self {
+        Self::builtin("endOfDay", offset.as_slice())
+    }
+
+    /// The start of the current week, optionally shifted by an offset such as `-1w`.
+    #[must_use]
+    pub fn start_of_week(offset: Option<&str>) -> Self {
+        Self::builtin("startOfWeek", offset.as_slice())
+    }
+
+    /// The end of the current week, optionally shifted by an offset.
+    #[must_use]
+    pub fn end_of_week(offset: Option<&str>) -> Self {
+        Self::builtin("endOfWeek", offset.as_slice())
+    }
+
+    /// The start of the current month, optionally shifted by an offset such as `-1M`.
+    #[must_use]
+    pub fn start_of_month(offset: Option<&str>) -> Self {
+        Self::builtin("startOfMonth", offset.as_slice())
+    }
+
+    /// The end of the current month, optionally shifted by an offset.
+    #[must_use]
+    pub fn end_of_month(offset: Option<&str>) -> Self {
+        Self::builtin("endOfMonth", offset.as_slice())
+    }
+}
+
+impl From<&str> for JqlValue {
+    fn from(text: &str) -> Self {
+        Self(ValueKind::Text(text.to_string()))
+    }
+}
+
+impl From<String> for JqlValue {
+    fn from(text: String) -> Self {
+        Self(ValueKind::Text(text))
+    }
+}
+
+impl From<&String> for JqlValue {
+    fn from(text: &String) -> Self {
+        Self(ValueKind::Text(text.clone()))
+    }
+}
+
+impl From<i64> for JqlValue {
+    fn from(number: i64) -> Self {
+        Self(ValueKind::Number(number.to_string()))
+    }
+}
+
+impl From<u32> for JqlValue {
+    fn from(number: u32) -> Self {
+        Self(ValueKind::Number(number.to_string()))
+    }
+}
+
+impl From<NaiveDate> for JqlValue {
+    fn from(date: NaiveDate) -> Self {
+        Self(ValueKind::Text(date.format("%Y-%m-%d").to_string()))
+    }
+}
+
+impl JqlHistory {
+    /// No conditions: any change at any time.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn predicate(mut self, keyword: &str, value: impl Into<JqlValue>) -> Self {
+        self.predicates.push(format!("{keyword} {}", value.into()));
+        self
+    }
+
+    /// The change happened after the date.
+    #[must_use]
+    pub fn after(self, date: impl Into<JqlValue>) -> Self {
+        self.predicate("AFTER", date)
+    }
+
+    /// The change happened before the date.
+    #[must_use]
+    pub fn before(self, date: impl Into<JqlValue>) -> Self {
+        self.predicate("BEFORE", date)
+    }
+
+    /// The change happened on the date.
+    #[must_use]
+    pub fn on(self, date: impl Into<JqlValue>) -> Self {
+        self.predicate("ON", date)
+    }
+
+    /// The change happened between the two dates.
+    #[must_use]
+    pub fn during(mut self, start: impl Into<JqlValue>, end: impl Into<JqlValue>) -> Self {
+        self.predicates
+            .push(format!("DURING ({}, {})", start.into(), end.into()));
+        self
+    }
+
+    /// The user, by their user name or account ID, made the change.
+    #[must_use]
+    pub fn by(self, user: impl Into<JqlValue>) -> Self {
+        self.predicate("BY", user)
+    }
+
+    /// The field changed from the value. Only applies to `CHANGED`.
+    #[must_use]
+    pub fn from(self, value: impl Into<JqlValue>) -> Self {
+        self.predicate("FROM", value)
+    }
+
+    /// The field changed to the value. Only applies to `CHANGED`.
+    #[must_use]
+    pub fn to(self, value: impl Into<JqlValue>) -> Self {
+        self.predicate("TO", value)
+    }
+}
+
+/// Quote the string, and escape the characters that can't appear in a quoted JQL string.
+pub(crate) fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+/// Render the field name, and quote it unless it's a plain word or a custom field ID.
+fn field_name(name: &str) -> String {
+    let is_word = name.chars().next().is_some_and(char::is_alphabetic)
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let is_keyword = KEYWORDS
+        .binary_search(&name.to_lowercase().as_str())
+        .is_ok();
+    let is_custom_id = name
+        .strip_prefix("cf[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()));
+
+    if (is_word && !is_keyword) || is_custom_id {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+impl fmt::Display for JqlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            ValueKind::Text(text) => write!(f, "{}", quote(text)),
+            ValueKind::Number(number) => write!(f, "{number}"),
+            ValueKind::Function { name, args } => write!(f, "{name}({})", args.join(", ")),
+        }
+    }
+}
+
+impl fmt::Display for JqlHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for predicate in &self.predicates {
+            write!(f, " {predicate}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Clause {
+    /// Render the clause. Parenthesize the nested clauses that bind more loosely
+    /// than their parent: `NOT` binds most tightly, then `AND`, then `OR`.
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Term(term) => write!(f, "{term}"),
+            // Every issue belongs to a project, so no issue matches.
+            Self::Nothing => write!(f, "project IS EMPTY"),
+            Self::And(clauses) => {
+                Self::join(f, clauses, " AND ", |clause| matches!(clause, Self::Or(_)))
+            }
+            Self::Or(clauses) => Self::join(f, clauses, " OR ", |_| false),
+            Self::Not(clause) => {
+                write!(f, "NOT ")?;
+                clause.render_grouped(f, !matches!(**clause, Self::Term(_)))
+            }
+        }
+    }
+
+    fn join(
+        f: &mut fmt::Formatter<'_>,
+        clauses: &[Self],
+        separator: &str,
+        needs_group: fn(&Self) -> bool,
+    ) -> fmt::Result {
+        for (index, clause) in clauses.iter().enumerate() {
+            if index > 0 {
+                write!(f, "{separator}")?;
+            }
+            clause.render_grouped(f, needs_group(clause))?;
+        }
+        Ok(())
+    }
+
+    fn render_grouped(&self, f: &mut fmt::Formatter<'_>, group: bool) -> fmt::Result {
+        if group {
+            write!(f, "(")?;
+            self.render(f)?;
+            write!(f, ")")
+        } else {
+            self.render(f)
+        }
+    }
+}
+
+impl fmt::Display for Jql {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(clause) = &self.clause {
+            clause.render(f)?;
+        }
+
+        if !self.order_by.is_empty() {
+            if self.clause.is_some() {
+                write!(f, " ")?;
+            }
+            let fields: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|(field, order)| match order {
+                    SortOrder::Asc => format!("{field} ASC"),
+                    SortOrder::Desc => format!("{field} DESC"),
+                })
+                .collect();
+            write!(f, "ORDER BY {}", fields.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        let query = Jql::field("summary")
+            .contains(r#"fails with "C:\temp""#)
+            .and(Jql::field("Target Release").eq("9.1"))
+            .and(Jql::field("cf[10016]").gt(5_i64))
+            .and(Jql::field("order").is_not_empty())
+            .and(Jql::field("size").lt(JqlValue::decimal(2.5).unwrap()))
+            .and(Jql::field("user").eq("jdoe"));
+
+        assert_eq!(
+            query.to_string(),
+            r#"summary ~ "fails with \"C:\\temp\"" AND "Target Release" = "9.1" AND cf[10016] > 5 AND "order" IS NOT EMPTY AND "size" < 2.5 AND "user" = "jdoe""#
+        );
+    }
+
+    #[test]
+    fn reserved_words() {
+        assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(field_name("Select"), r#""Select""#);
+        assert_eq!(field_name("assignee"), "assignee");
+    }
+
+    #[test]
+    fn non_finite_numbers() {
+        assert_eq!(JqlValue::decimal(1.5).unwrap().to_string(), "1.5");
+        assert!(JqlValue::decimal(f64::NAN).is_none());
+        assert!(JqlValue::decimal(f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn function_names() {
+        assert_eq!(
+            JqlValue::function("membersOf", &["jira-users"])
+                .unwrap()
+                .to_string(),
+            r#"membersOf("jira-users")"#
+        );
+        assert!(JqlValue::function("", &[]).is_none());
+        assert!(JqlValue::function("1st", &[]).is_none());
+        assert!(JqlValue::function("now() OR project = SECRET OR now", &[]).is_none());
+    }
+
+    #[test]
+    fn grouping() {
+        let query = Jql::field("project")
+            .eq("TEST")
+            .and(
+                Jql::field("priority")
+                    .eq("High")
+                    .or(Jql::field("labels").eq("urgent")),
+            )
+            .and(
+                !Jql::field("status")
+                    .is_in(["Closed", "Done"])
+                    .and(Jql::field("assignee").is_empty()),
+            );
+
+        assert_eq!(
+            query.to_string(),
+            r#"project = "TEST" AND (priority = "High" OR labels = "urgent") AND NOT (status IN ("Closed", "Done") AND assignee IS EMPTY)"#
+        );
+    }
+
+    #[test]
+    fn history() {
+        let query = Jql::field("status")
+            .changed(
+                &JqlHistory::new()
+                    .from("In Progress")
+                    .to("Closed")
+                    .after(JqlValue::start_of_day(Some("-7d"))),
+            )
+            .or(Jql::field("assignee").was(
+                JqlValue::current_user(),
+                &JqlHistory::new().during("2022-01-01", "2022-03-31"),
+            ))
+            .order_by("updated", SortOrder::Desc)
+            .order_by("key", SortOrder::Asc);
+
+        assert_eq!(
+            query.to_string(),
+            r#"status CHANGED FROM "In Progress" TO "Closed" AFTER startOfDay("-7d") OR assignee WAS currentUser() DURING ("2022-01-01", "2022-03-31") ORDER BY updated DESC, key ASC"#
+        );
+        assert_eq!(
+            Jql::new().order_by("created", SortOrder::Asc).to_string(),
+            "ORDER BY created ASC"
+        );
+    }
+
+    #[test]
+    fn identities() {
+        let term = || Jql::field("status").eq("Closed");
+
+        assert_eq!(Jql::new().and(term()), term());
+        assert_eq!(term().and(Jql::new()), term());
+        assert_eq!(Jql::new().or(term()), Jql::new());
+        assert_eq!(term().or(Jql::new()), Jql::new());
+        assert_eq!((!Jql::new()).to_string(), "project IS EMPTY");
+        assert_eq!(!!Jql::new(), Jql::new());
+        assert_eq!((!Jql::new()).or(term()), term());
+        assert_eq!(term().and(!Jql::new()), !Jql::new());
+    }
+
+    #[test]
+    fn empty_lists() {
+        let none: [&str; 0] = [];
+
+        assert_eq!(
+            Jql::field("status").is_in(none).to_string(),
+            "project IS EMPTY"
+        );
+        assert_eq!(Jql::field("status").not_in(none), Jql::new());
+        assert_eq!(
+            Jql::field("status")
+                .was_in(none, &JqlHistory::new())
+                .or(Jql::field("assignee").is_empty())
+                .to_string(),
+            "assignee IS EMPTY"
+        );
+    }
+}
@@ -26,16 +26,56 @@ limitations under the License.
 #![forbid(unsafe_code)]
 
 mod access;
+mod adf;
+mod agile;
+mod analytics;
+mod custom_fields;
+mod edit;
 mod errors;
+mod fields;
+mod graph;
 mod issue_model;
+mod jql;
+mod links;
+mod markup;
+mod options;
+mod retry;
+mod workflow;
+mod worklog;
 
-pub use access::{Auth, JiraInstance, Pagination};
+pub use access::{ApiVersion, Auth, Deployment, FoundIssues, JiraInstance, Pagination};
+pub use adf::{
+    AdfMark, AdfNode, CardAttrs, CodeBlockAttrs, EmojiAttrs, HeadingAttrs, LinkAttrs, MediaAttrs,
+    MentionAttrs, OrderedListAttrs, RichText,
+};
+pub use agile::{
+    Board, BoardColumn, BoardConfiguration, BoardFilter, BoardLocation, ColumnConfig, ColumnStatus,
+    Epic, EpicColor,
+};
+pub use analytics::{AggregateMetrics, DurationStats, IssueMetrics, StatusInterval, Workflow};
+pub use custom_fields::{CascadingSelect, CustomFields, FromCustomField, SelectOption};
+pub use edit::{CreatedIssue, IssueEdit, UserRef};
 pub use errors::JiraQueryError;
+pub use fields::{FieldDefinition, FieldRegistry, FieldSchema};
+pub use graph::{Edge, GraphOptions, IssueGraph, LinkDirection, Relation};
 pub use issue_model::{
-    AvatarUrls, Comment, Comments, Component, CondensedFields, CondensedIssue, Fields, Issue,
-    IssueLink, IssueLinkType, IssueType, LinkedIssue, LinkedIssueFields, Priority, Progress,
-    Project, ProjectCategory, Resolution, Status, StatusCategory, User, Version, Visibility, Votes,
-    Watches,
+    Attachment, AvatarUrls, ChangeHistory, ChangeItem, Changelog, Comment, Comments, Component,
+    CondensedFields, CondensedIssue, Fields, Issue, IssueLink, IssueLinkType, IssueRepresentation,
+    IssueSprints, IssueType, JqlResults, LinkedIssue, LinkedIssueFields, PartialFields,
+    PartialIssue, Priority, Progress, Project, ProjectCategory, Resolution, Sprint, SprintState,
+    Status, StatusCategory, User, Version, Visibility, Votes, Watches,
+};
+pub use jql::{Jql, JqlField, JqlHistory, JqlValue, SortOrder};
+pub use links::{
+    CreatedRemoteLink, RemoteApplication, RemoteIcon, RemoteLink, RemoteLinkEdit, RemoteObject,
+    RemoteStatus,
 };
+pub use markup::{WikiBlock, WikiDocument, WikiInline, WikiList, WikiListItem, WikiTableCell};
+pub use options::{Expand, RequestOptions};
+pub use retry::RetryPolicy;
+pub use workflow::{Transition, TransitionField, TransitionRequest};
+pub use worklog::{EstimateAdjustment, UpdatedWorklogs, Worklog, WorklogEntry};
 // Re-export JSON Value because it's an integral part of the issue model.
 pub use serde_json::Value;
+// Re-export URL because it's the type of URL custom fields.
+pub use url::Url;
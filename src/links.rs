@@ -0,0 +1,182 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module models the remote links from issues to external resources,
+// such as pull requests or builds, and the requests that link issues to each other.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::issue_model::IssueLinkType;
+
+/// A link from a Jira issue to an external resource, such as a pull request.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteLink {
+    pub id: u64,
+    /// The ID that identifies the resource across Jira, such as its URL.
+    #[serde(rename = "globalId")]
+    pub global_id: Option<String>,
+    pub application: Option<RemoteApplication>,
+    /// The relationship of the issue to the resource, such as `mentioned in`.
+    pub relationship: Option<String>,
+    pub object: RemoteObject,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The application that provides the linked resource, such as GitHub.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteApplication {
+    /// The type of the application, such as `com.github`.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub application_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// The linked resource.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteObject {
+    pub url: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<RemoteIcon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RemoteStatus>,
+}
+
+/// A small icon that Jira displays next to a remote link.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteIcon {
+    /// The URL of a 16×16 pixel image.
+    #[serde(rename = "url16x16", skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// The status of the linked resource.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteStatus {
+    /// Whether the resource is finished, such as a merged pull request.
+    /// Jira strikes through resolved links.
+    pub resolved: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<RemoteIcon>,
+}
+
+/// The content of a new remote link, or the replacement of an existing remote link.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RemoteLinkEdit {
+    #[serde(rename = "globalId", skip_serializing_if = "Option::is_none")]
+    global_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    application: Option<RemoteApplication>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relationship: Option<String>,
+    object: RemoteObject,
+}
+
+impl RemoteLinkEdit {
+    /// A link to the URL with the title.
+    #[must_use]
+    pub fn new(url: &str, title: &str) -> Self {
+        Self {
+            global_id: None,
+            application: None,
+            relationship: None,
+            object: RemoteObject {
+                url: url.to_string(),
+                title: title.to_string(),
+                summary: None,
+                icon: None,
+                status: None,
+            },
+        }
+    }
+
+    /// Identify the resource, such as by its URL. If the issue already links to a resource
+    /// with the same global ID, the link replaces the existing link.
+    #[must_use]
+    pub fn global_id(mut self, global_id: &str) -> Self {
+        self.global_id = Some(global_id.to_string());
+        self
+    }
+
+    /// Set the application that provides the resource, by its type and name,
+    /// such as `com.github` and `GitHub`.
+    #[must_use]
+    pub fn application(mut self, application_type: &str, name: &str) -> Self {
+        self.application = Some(RemoteApplication {
+            application_type: Some(application_type.to_string()),
+            name: Some(name.to_string()),
+        });
+        self
+    }
+
+    /// Describe the relationship of the issue to the resource, such as `mentioned in`.
+    #[must_use]
+    pub fn relationship(mut self, relationship: &str) -> Self {
+        self.relationship = Some(relationship.to_string());
+        self
+    }
+
+    /// Add a short description of the resource.
+    #[must_use]
+    pub fn summary(mut self, summary: &str) -> Self {
+        self.object.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Display the icon at the URL next to the link.
+    #[must_use]
+    pub fn icon(mut self, url: &str, title: &str) -> Self {
+        self.object.icon = Some(RemoteIcon {
+            url: Some(url.to_string()),
+            title: Some(title.to_string()),
+        });
+        self
+    }
+
+    /// Mark the resource as finished or unfinished.
+    #[must_use]
+    pub fn resolved(mut self, resolved: bool) -> Self {
+        self.object.status = Some(RemoteStatus {
+            resolved,
+            icon: None,
+        });
+        self
+    }
+}
+
+/// The identification of a remote link that Jira has just created or updated.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CreatedRemoteLink {
+    pub id: u64,
+    #[serde(rename = "self")]
+    pub self_link: String,
+}
+
+/// The list of link types that Jira returns.
+#[derive(Debug, Deserialize)]
+pub(crate) struct IssueLinkTypes {
+    #[serde(rename = "issueLinkTypes")]
+    pub issue_link_types: Vec<IssueLinkType>,
+}
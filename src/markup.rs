@@ -0,0 +1,1035 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module parses the Jira wiki markup, which API v2 uses for rich text fields,
+// and renders it to other formats.
+// See https://jira.atlassian.com/secure/WikiRendererHelpAction.jspa?section=all
+
+use std::cmp::Ordering;
+
+/// A rich text field parsed from the Jira wiki markup.
+///
+/// Like Jira itself, the parser never fails. Markup that it doesn't recognize
+/// stays in the document as literal text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WikiDocument {
+    pub blocks: Vec<WikiBlock>,
+}
+
+/// A block-level element of the wiki markup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WikiBlock {
+    /// `h1.` to `h6.`
+    Heading { level: u8, content: Vec<WikiInline> },
+    /// Consecutive lines of text.
+    Paragraph(Vec<WikiInline>),
+    /// `{code}` or `{code:language}`
+    Code {
+        language: Option<String>,
+        code: String,
+    },
+    /// `{noformat}`
+    Preformatted(String),
+    /// `bq.` or `{quote}`
+    Quote(Vec<WikiBlock>),
+    /// Lines that start with `*`, `-`, or `#`.
+    List(WikiList),
+    /// Lines that start with `|`, or `||` for header cells.
+    Table(Vec<Vec<WikiTableCell>>),
+    /// `----`
+    Rule,
+}
+
+/// A bulleted or numbered list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WikiList {
+    pub ordered: bool,
+    pub items: Vec<WikiListItem>,
+}
+
+/// An item of a list, with the lists nested below it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WikiListItem {
+    pub content: Vec<WikiInline>,
+    pub sublists: Vec<WikiList>,
+}
+
+/// A single cell of a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WikiTableCell {
+    pub header: bool,
+    pub content: Vec<WikiInline>,
+}
+
+/// An inline element of the wiki markup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WikiInline {
+    Text(String),
+    /// `*strong*`
+    Strong(Vec<WikiInline>),
+    /// `_emphasis_`
+    Emphasis(Vec<WikiInline>),
+    /// `-deleted-`
+    Strikethrough(Vec<WikiInline>),
+    /// `+inserted+`
+    Underline(Vec<WikiInline>),
+    /// `^superscript^`
+    Superscript(Vec<WikiInline>),
+    /// `~subscript~`
+    Subscript(Vec<WikiInline>),
+    /// `??citation??`
+    Citation(Vec<WikiInline>),
+    /// `{{monospaced}}`
+    Monospace(String),
+    /// `[url]` or `[text|url]`. Without text, the link shows the URL.
+    Link {
+        text: Vec<WikiInline>,
+        url: String,
+    },
+    /// `[~username]`
+    Mention(String),
+    /// `!image.png!` or `!image.png|thumbnail!`
+    Image(String),
+    /// `\\` or a new line within a paragraph.
+    LineBreak,
+}
+
+impl WikiDocument {
+    /// Parse a rich text field in the Jira wiki markup.
+    #[must_use]
+    pub fn parse(markup: &str) -> Self {
+        let lines: Vec<&str> = markup.lines().collect();
+        Self {
+            blocks: parse_blocks(&lines),
+        }
+    }
+
+    /// Render the document as Markdown.
+    ///
+    /// The output follows `CommonMark` with the GitHub extensions for tables
+    /// and strikethrough. Formatting that Markdown lacks, such as underline,
+    /// uses inline HTML.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        join_nonempty(self.blocks.iter().map(WikiBlock::to_markdown), "\n\n")
+    }
+
+    /// Render the document as an HTML fragment.
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        join_nonempty(self.blocks.iter().map(WikiBlock::to_html), "\n")
+    }
+
+    /// The text content of the document, without any formatting.
+    ///
+    /// Blocks are separated by an empty line, list items start on a new line,
+    /// and table cells are separated by a tab.
+    #[must_use]
+    pub fn to_plain_text(&self) -> String {
+        join_nonempty(self.blocks.iter().map(WikiBlock::to_plain_text), "\n\n")
+    }
+}
+
+// Parsing blocks
+
+/// Parse the lines of markup into blocks.
+fn parse_blocks(lines: &[&str]) -> Vec<WikiBlock> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i].trim();
+
+        if line.is_empty() {
+            i += 1;
+        } else if let Some(rest) = macro_start(line, "code") {
+            let (params, first_line) = split_macro_params(rest);
+            let (code, next) = macro_body(lines, i, first_line, "{code}");
+            let language = params
+                .split('|')
+                .map(|param| param.strip_prefix("language=").unwrap_or(param))
+                .find(|param| !param.is_empty() && !param.contains('='))
+                .map(str::to_string);
+            blocks.push(WikiBlock::Code { language, code });
+            i = next;
+        } else if let Some(rest) = macro_start(line, "noformat") {
+            let (_, first_line) = split_macro_params(rest);
+            let (text, next) = macro_body(lines, i, first_line, "{noformat}");
+            blocks.push(WikiBlock::Preformatted(text));
+            i = next;
+        } else if let Some(rest) = line.strip_prefix("{quote}") {
+            let (text, next) = macro_body(lines, i, rest, "{quote}");
+            let inner: Vec<&str> = text.lines().collect();
+            blocks.push(WikiBlock::Quote(parse_blocks(&inner)));
+            i = next;
+        } else if let Some(rest) = line.strip_prefix("bq. ") {
+            blocks.push(WikiBlock::Quote(vec![WikiBlock::Paragraph(parse_inline(
+                rest.trim(),
+            ))]));
+            i += 1;
+        } else if let Some((level, rest)) = heading(line) {
+            blocks.push(WikiBlock::Heading {
+                level,
+                content: parse_inline(rest.trim()),
+            });
+            i += 1;
+        } else if line == "----" {
+            blocks.push(WikiBlock::Rule);
+            i += 1;
+        } else if list_item(line).is_some() {
+            let mut entries = Vec::new();
+            while let Some(entry) = lines.get(i).and_then(|line| list_item(line.trim())) {
+                entries.push(entry);
+                i += 1;
+            }
+            let mut position = 0;
+            while position < entries.len() {
+                blocks.push(WikiBlock::List(parse_list(&entries, &mut position, 1)));
+            }
+        } else if line.starts_with('|') {
+            let mut rows = Vec::new();
+            while let Some(line) = lines.get(i).map(|line| line.trim()) {
+                if !line.starts_with('|') {
+                    break;
+                }
+                rows.push(table_row(line));
+                i += 1;
+            }
+            blocks.push(WikiBlock::Table(rows));
+        } else {
+            let mut paragraph = vec![line];
+            i += 1;
+            while let Some(line) = lines.get(i).map(|line| line.trim()) {
+                if line.is_empty() || starts_block(line) {
+                    break;
+                }
+                paragraph.push(line);
+                i += 1;
+            }
+            blocks.push(WikiBlock::Paragraph(parse_inline(&paragraph.join("\n"))));
+        }
+    }
+
+    blocks
+}
+
+/// Whether the line starts a block other than a paragraph.
+fn starts_block(line: &str) -> bool {
+    macro_start(line, "code").is_some()
+        || macro_start(line, "noformat").is_some()
+        || line.starts_with("{quote}")
+        || line.starts_with("bq. ")
+        || heading(line).is_some()
+        || line == "----"
+        || list_item(line).is_some()
+        || line.starts_with('|')
+}
+
+/// If the line opens the macro, such as `{code:java}`, return the text after the macro name.
+fn macro_start<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix('{')?.strip_prefix(name)?;
+    (rest.starts_with(':') || rest.starts_with('}')).then_some(rest)
+}
+
+/// Split the text after a macro name into the macro parameters and the rest of the line.
+fn split_macro_params(rest: &str) -> (&str, &str) {
+    match rest.split_once('}') {
+        Some((params, after)) => (params.trim_start_matches(':'), after),
+        None => ("", ""),
+    }
+}
+
+/// Collect the content of a macro up to its closing tag, starting with the rest
+/// of the opening line. Return the content and the index of the line after the macro.
+/// An unclosed macro extends to the end of the markup.
+fn macro_body(lines: &[&str], start: usize, first_line: &str, closing: &str) -> (String, usize) {
+    if let Some((body, _)) = first_line.split_once(closing) {
+        return (body.to_string(), start + 1);
+    }
+
+    let mut body: Vec<&str> = Vec::new();
+    if !first_line.trim().is_empty() {
+        body.push(first_line);
+    }
+
+    let mut i = start + 1;
+    while i < lines.len() {
+        if let Some((last, _)) = lines[i].split_once(closing) {
+            if !last.trim().is_empty() {
+                body.push(last);
+            }
+            return (body.join("\n"), i + 1);
+        }
+        body.push(lines[i]);
+        i += 1;
+    }
+
+    (body.join("\n"), i)
+}
+
+/// If the line is a heading, return its level and text.
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let rest = line.strip_prefix('h')?;
+    let level = rest.chars().next()?.to_digit(10)?;
+    let text = rest[1..].strip_prefix(". ")?;
+    let level = u8::try_from(level).ok().filter(|l| (1..=6).contains(l))?;
+    Some((level, text))
+}
+
+/// If the line is a list item, return its markers, such as `#*`, and its text.
+fn list_item(line: &str) -> Option<(&str, &str)> {
+    let (markers, text) = line.split_once(' ')?;
+    let valid = if markers == "-" {
+        true
+    } else {
+        !markers.is_empty() && markers.chars().all(|c| c == '*' || c == '#')
+    };
+    valid.then_some((markers, text))
+}
+
+/// Build a list from the item at `position` and all following items at this depth or deeper.
+fn parse_list(entries: &[(&str, &str)], position: &mut usize, depth: usize) -> WikiList {
+    let ordered = entries[*position].0.as_bytes().get(depth - 1) == Some(&b'#');
+    let mut list = WikiList {
+        ordered,
+        items: Vec::new(),
+    };
+
+    while let Some((markers, text)) = entries.get(*position) {
+        match markers.len().cmp(&depth) {
+            Ordering::Less => break,
+            Ordering::Equal => {
+                // A different kind of marker at the same depth starts a new list.
+                if (markers.as_bytes()[depth - 1] == b'#') != ordered {
+                    break;
+                }
+                list.items.push(WikiListItem {
+                    content: parse_inline(text.trim()),
+                    sublists: Vec::new(),
+                });
+                *position += 1;
+            }
+            Ordering::Greater => {
+                let sublist = parse_list(entries, position, depth + 1);
+                if list.items.is_empty() {
+                    list.items.push(WikiListItem::default());
+                }
+                if let Some(item) = list.items.last_mut() {
+                    item.sublists.push(sublist);
+                }
+            }
+        }
+    }
+
+    list
+}
+
+/// Split a table row into cells. Cells that start with `||` are header cells.
+fn table_row(line: &str) -> Vec<WikiTableCell> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut cells = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let header = chars.get(i + 1) == Some(&'|');
+        i += if header { 2 } else { 1 };
+
+        // A `|` within a link or a macro doesn't separate cells.
+        let start = i;
+        let mut nesting = 0_i32;
+        while i < chars.len() && (chars[i] != '|' || nesting > 0) {
+            match chars[i] {
+                '[' | '{' => nesting += 1,
+                ']' | '}' => nesting -= 1,
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let content: String = chars[start..i].iter().collect();
+        if i >= chars.len() && content.trim().is_empty() {
+            break;
+        }
+        cells.push(WikiTableCell {
+            header,
+            content: parse_inline(content.trim()),
+        });
+    }
+
+    cells
+}
+
+// Parsing inline elements
+
+/// Parse the text of a block into inline elements.
+fn parse_inline(text: &str) -> Vec<WikiInline> {
+    let chars: Vec<char> = text.chars().collect();
+    InlineParser {
+        chars: &chars,
+        inlines: Vec::new(),
+        text: String::new(),
+    }
+    .parse()
+}
+
+/// The state of parsing a run of inline elements.
+struct InlineParser<'a> {
+    chars: &'a [char],
+    inlines: Vec<WikiInline>,
+    /// The literal text since the last inline element.
+    text: String,
+}
+
+impl InlineParser<'_> {
+    fn parse(mut self) -> Vec<WikiInline> {
+        let chars = self.chars;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+
+            let parsed = match c {
+                '\\' if next == Some('\\') => Some((WikiInline::LineBreak, i + 2)),
+                '\\' if next.is_some() => {
+                    self.text.push(chars[i + 1]);
+                    i += 2;
+                    continue;
+                }
+                '\n' => Some((WikiInline::LineBreak, i + 1)),
+                '{' if next == Some('{') => find(chars, i + 2, &['}', '}'])
+                    .map(|end| (WikiInline::Monospace(collect(&chars[i + 2..end])), end + 2)),
+                // Colors have no equivalent in the output formats. Keep the colored text.
+                '{' if starts_with(&chars[i..], "{color") => {
+                    if let Some(end) = find(chars, i, &['}']) {
+                        i = end + 1;
+                        continue;
+                    }
+                    None
+                }
+                '[' => find(chars, i + 1, &[']']).map(|end| (link(&chars[i + 1..end]), end + 1)),
+                '!' => image(chars, i),
+                '?' if next == Some('?') => self
+                    .delimited(i, 2, false)
+                    .map(|(inner, end)| (WikiInline::Citation(inner), end)),
+                '*' | '_' | '-' | '+' | '^' | '~' => {
+                    // Superscript and subscript commonly appear within words, such as `x^2^`.
+                    let intraword = matches!(c, '^' | '~');
+                    self.delimited(i, 1, intraword).map(|(inner, end)| {
+                        let inline = match c {
+                            '*' => WikiInline::Strong(inner),
+                            '_' => WikiInline::Emphasis(inner),
+                            '-' => WikiInline::Strikethrough(inner),
+                            '+' => WikiInline::Underline(inner),
+                            '^' => WikiInline::Superscript(inner),
+                            _ => WikiInline::Subscript(inner),
+                        };
+                        (inline, end)
+                    })
+                }
+                _ => None,
+            };
+
+            if let Some((inline, end)) = parsed {
+                self.flush();
+                self.inlines.push(inline);
+                i = end;
+            } else {
+                self.text.push(c);
+                i += 1;
+            }
+        }
+
+        self.flush();
+        self.inlines
+    }
+
+    /// Move the pending literal text to the inline elements.
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.inlines
+                .push(WikiInline::Text(std::mem::take(&mut self.text)));
+        }
+    }
+
+    /// Parse formatting that starts at `start` with a delimiter of the specified length,
+    /// such as `*strong*`. Return the parsed content and the position after the closing delimiter.
+    ///
+    /// The opening delimiter must not precede a space, and the closing delimiter
+    /// must not follow a space. Unless the formatting is `intraword`, the opening
+    /// delimiter must also not follow a letter, and the closing delimiter must not
+    /// precede a letter. That way, `well-known` or `2 * 3` stay literal.
+    fn delimited(
+        &self,
+        start: usize,
+        length: usize,
+        intraword: bool,
+    ) -> Option<(Vec<WikiInline>, usize)> {
+        let chars = self.chars;
+        let delimiter = &chars[start..start + length];
+
+        let after_word = !intraword && start > 0 && chars[start - 1].is_alphanumeric();
+        let content_start = start + length;
+        let first = chars.get(content_start)?;
+        if after_word || first.is_whitespace() || *first == delimiter[0] {
+            return None;
+        }
+
+        let mut end = content_start + 1;
+        while end + length <= chars.len() {
+            if chars[end] == '\n' {
+                return None;
+            }
+            let closes = &chars[end..end + length] == delimiter
+                && !chars[end - 1].is_whitespace()
+                && (intraword
+                    || chars
+                        .get(end + length)
+                        .map_or(true, |c| !c.is_alphanumeric()));
+            if closes {
+                let inner = parse_inline(&collect(&chars[content_start..end]));
+                return Some((inner, end + length));
+            }
+            end += 1;
+        }
+
+        None
+    }
+}
+
+/// Find the first occurrence of the pattern at or after `from`, on the same line.
+fn find(chars: &[char], from: usize, pattern: &[char]) -> Option<usize> {
+    (from..chars.len())
+        .take_while(|&i| chars[i] != '\n')
+        .find(|&i| chars[i..].starts_with(pattern))
+}
+
+/// Whether the characters start with the string.
+fn starts_with(chars: &[char], prefix: &str) -> bool {
+    prefix
+        .chars()
+        .enumerate()
+        .all(|(i, c)| chars.get(i) == Some(&c))
+}
+
+fn collect(chars: &[char]) -> String {
+    chars.iter().collect()
+}
+
+/// Parse the content between `[` and `]`.
+fn link(content: &[char]) -> WikiInline {
+    let content = collect(content);
+
+    if let Some(user) = content.strip_prefix('~') {
+        return WikiInline::Mention(user.to_string());
+    }
+
+    match content.split_once('|') {
+        Some((text, url)) => WikiInline::Link {
+            text: parse_inline(text.trim()),
+            url: url.trim().to_string(),
+        },
+        None => WikiInline::Link {
+            text: Vec::new(),
+            url: content.trim().to_string(),
+        },
+    }
+}
+
+/// Parse an image that starts at `start`, such as `!screenshot.png|thumbnail!`.
+/// The image source can't contain spaces, so that exclamations in a sentence stay literal.
+fn image(chars: &[char], start: usize) -> Option<(WikiInline, usize)> {
+    let end = find(chars, start + 1, &['!'])?;
+    let content = collect(&chars[start + 1..end]);
+
+    if content.is_empty() || content.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let source = content.split('|').next().unwrap_or_default();
+    Some((WikiInline::Image(source.to_string()), end + 1))
+}
+
+// Rendering
+
+impl WikiBlock {
+    fn to_markdown(&self) -> String {
+        match self {
+            Self::Heading { level, content } => {
+                format!("{} {}", "#".repeat(usize::from(*level)), markdown(content))
+            }
+            Self::Paragraph(content) => markdown_block(content),
+            Self::Code { language, code } => {
+                let fence = code_fence(code);
+                format!(
+                    "{fence}{}\n{code}\n{fence}",
+                    language.as_deref().unwrap_or_default()
+                )
+            }
+            Self::Preformatted(text) => {
+                let fence = code_fence(text);
+                format!("{fence}\n{text}\n{fence}")
+            }
+            Self::Quote(blocks) => {
+                let inner = join_nonempty(blocks.iter().map(Self::to_markdown), "\n\n");
+                inner
+                    .lines()
+                    .map(|line| {
+                        if line.is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {line}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Self::List(list) => list.to_markdown(""),
+            Self::Table(rows) => {
+                let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+                let row = |cells: &[WikiTableCell]| {
+                    let mut cells: Vec<String> =
+                        cells.iter().map(|cell| markdown(&cell.content)).collect();
+                    cells.resize(columns, String::new());
+                    format!("| {} |", cells.join(" | "))
+                };
+
+                // Markdown tables require a header row.
+                let (header, body) = match rows.split_first() {
+                    Some((first, rest)) if first.iter().all(|cell| cell.header) => {
+                        (row(first), rest)
+                    }
+                    _ => (row(&[]), rows.as_slice()),
+                };
+                let separator = format!("|{}", " --- |".repeat(columns));
+
+                let mut lines = vec![header, separator];
+                lines.extend(body.iter().map(|cells| row(cells)));
+                lines.join("\n")
+            }
+            Self::Rule => "---".to_string(),
+        }
+    }
+
+    fn to_html(&self) -> String {
+        match self {
+            Self::Heading { level, content } => {
+                format!("<h{level}>{}</h{level}>", html(content))
+            }
+            Self::Paragraph(content) => format!("<p>{}</p>", html(content)),
+            Self::Code { language, code } => match language {
+                Some(language) => format!(
+                    "<pre><code class=\"language-{}\">{}</code></pre>",
+                    escape_html(language),
+                    escape_html(code)
+                ),
+                None => format!("<pre><code>{}</code></pre>", escape_html(code)),
+            },
+            Self::Preformatted(text) => format!("<pre>{}</pre>", escape_html(text)),
+            Self::Quote(blocks) => format!(
+                "<blockquote>\n{}\n</blockquote>",
+                join_nonempty(blocks.iter().map(Self::to_html), "\n")
+            ),
+            Self::List(list) => list.to_html(),
+            Self::Table(rows) => {
+                let rows: Vec<String> = rows
+                    .iter()
+                    .map(|cells| {
+                        let cells: Vec<String> = cells
+                            .iter()
+                            .map(|cell| {
+                                let tag = if cell.header { "th" } else { "td" };
+                                format!("<{tag}>{}</{tag}>", html(&cell.content))
+                            })
+                            .collect();
+                        format!("<tr>{}</tr>", cells.concat())
+                    })
+                    .collect();
+                format!("<table>\n{}\n</table>", rows.join("\n"))
+            }
+            Self::Rule => "<hr>".to_string(),
+        }
+    }
+
+    fn to_plain_text(&self) -> String {
+        match self {
+            Self::Heading { content, .. } | Self::Paragraph(content) => plain_text(content),
+            Self::Code { code: text, .. } | Self::Preformatted(text) => text.clone(),
+            Self::Quote(blocks) => join_nonempty(blocks.iter().map(Self::to_plain_text), "\n\n"),
+            Self::List(list) => list.to_plain_text(""),
+            Self::Table(rows) => rows
+                .iter()
+                .map(|cells| {
+                    cells
+                        .iter()
+                        .map(|cell| plain_text(&cell.content))
+                        .collect::<Vec<_>>()
+                        .join("\t")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Rule => String::new(),
+        }
+    }
+}
+
+impl WikiList {
+    /// The marker of the item at the zero-based index.
+    fn marker(&self, index: usize) -> String {
+        if self.ordered {
+            format!("{}. ", index + 1)
+        } else {
+            "- ".to_string()
+        }
+    }
+
+    fn to_markdown(&self, indent: &str) -> String {
+        let mut lines = Vec::new();
+
+        for (index, item) in self.items.iter().enumerate() {
+            let marker = self.marker(index);
+            // Markdown nests the continuation lines and lists under the text of the parent item.
+            let nested = format!("{indent}{}", " ".repeat(marker.len()));
+            let text = markdown_block(&item.content).replace('\n', &format!("\n{nested}"));
+            lines.push(format!("{indent}{marker}{text}"));
+            lines.extend(item.sublists.iter().map(|list| list.to_markdown(&nested)));
+        }
+
+        lines.join("\n")
+    }
+
+    fn to_html(&self) -> String {
+        let tag = if self.ordered { "ol" } else { "ul" };
+        let items: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| {
+                let sublists: Vec<String> = item
+                    .sublists
+                    .iter()
+                    .map(|list| format!("\n{}\n", list.to_html()))
+                    .collect();
+                format!("<li>{}{}</li>", html(&item.content), sublists.concat())
+            })
+            .collect();
+
+        format!("<{tag}>\n{}\n</{tag}>", items.join("\n"))
+    }
+
+    fn to_plain_text(&self, indent: &str) -> String {
+        let mut lines = Vec::new();
+
+        for (index, item) in self.items.iter().enumerate() {
+            let marker = self.marker(index);
+            let nested = format!("{indent}{}", " ".repeat(marker.len()));
+            let text = plain_text(&item.content).replace('\n', &format!("\n{nested}"));
+            lines.push(format!("{indent}{marker}{text}"));
+            lines.extend(item.sublists.iter().map(|list| list.to_plain_text(&nested)));
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn markdown(inlines: &[WikiInline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            WikiInline::Text(text) => escape_markdown(text),
+            WikiInline::Strong(inner) => format!("**{}**", markdown(inner)),
+            WikiInline::Emphasis(inner) | WikiInline::Citation(inner) => {
+                format!("*{}*", markdown(inner))
+            }
+            WikiInline::Strikethrough(inner) => format!("~~{}~~", markdown(inner)),
+            WikiInline::Underline(inner) => format!("<u>{}</u>", markdown(inner)),
+            WikiInline::Superscript(inner) => format!("<sup>{}</sup>", markdown(inner)),
+            WikiInline::Subscript(inner) => format!("<sub>{}</sub>", markdown(inner)),
+            WikiInline::Monospace(code) => {
+                if code.contains('`') {
+                    format!("`` {code} ``")
+                } else {
+                    format!("`{code}`")
+                }
+            }
+            WikiInline::Link { text, url } if !is_safe_url(url) => {
+                if text.is_empty() {
+                    escape_markdown(url)
+                } else {
+                    markdown(text)
+                }
+            }
+            WikiInline::Link { text, url } if text.is_empty() => {
+                if url.contains("://") {
+                    format!("<{}>", markdown_url(url))
+                } else {
+                    format!("[{}]({})", escape_markdown(url), markdown_url(url))
+                }
+            }
+            WikiInline::Link { text, url } => {
+                format!("[{}]({})", markdown(text), markdown_url(url))
+            }
+            WikiInline::Mention(user) => format!("@{}", escape_markdown(user)),
+            WikiInline::Image(source) if is_safe_url(source) => {
+                format!("![]({})", markdown_url(source))
+            }
+            WikiInline::Image(source) => escape_markdown(source),
+            WikiInline::LineBreak => "\\\n".to_string(),
+        })
+        .collect()
+}
+
+fn html(inlines: &[WikiInline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            WikiInline::Text(text) => escape_html(text),
+            WikiInline::Strong(inner) => format!("<strong>{}</strong>", html(inner)),
+            WikiInline::Emphasis(inner) => format!("<em>{}</em>", html(inner)),
+            WikiInline::Strikethrough(inner) => format!("<del>{}</del>", html(inner)),
+            WikiInline::Underline(inner) => format!("<u>{}</u>", html(inner)),
+            WikiInline::Superscript(inner) => format!("<sup>{}</sup>", html(inner)),
+            WikiInline::Subscript(inner) => format!("<sub>{}</sub>", html(inner)),
+            WikiInline::Citation(inner) => format!("<cite>{}</cite>", html(inner)),
+            WikiInline::Monospace(code) => format!("<code>{}</code>", escape_html(code)),
+            WikiInline::Link { text, url } => {
+                let text = if text.is_empty() {
+                    escape_html(url)
+                } else {
+                    html(text)
+                };
+                if is_safe_url(url) {
+                    format!("<a href=\"{}\">{text}</a>", escape_html(url))
+                } else {
+                    text
+                }
+            }
+            WikiInline::Mention(user) => format!("@{}", escape_html(user)),
+            WikiInline::Image(source) if is_safe_url(source) => {
+                format!("<img src=\"{}\" alt=\"\">", escape_html(source))
+            }
+            WikiInline::Image(source) => escape_html(source),
+            WikiInline::LineBreak => "<br>\n".to_string(),
+        })
+        .collect()
+}
+
+fn plain_text(inlines: &[WikiInline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            WikiInline::Text(text) | WikiInline::Monospace(text) => text.clone(),
+            WikiInline::Strong(inner)
+            | WikiInline::Emphasis(inner)
+            | WikiInline::Strikethrough(inner)
+            | WikiInline::Underline(inner)
+            | WikiInline::Superscript(inner)
+            | WikiInline::Subscript(inner)
+            | WikiInline::Citation(inner) => plain_text(inner),
+            WikiInline::Link { text, url } if text.is_empty() => url.clone(),
+            WikiInline::Link { text, .. } => plain_text(text),
+            WikiInline::Mention(user) => format!("@{user}"),
+            WikiInline::Image(_) => String::new(),
+            WikiInline::LineBreak => "\n".to_string(),
+        })
+        .collect()
+}
+
+/// Choose a code fence that doesn't occur in the code.
+fn code_fence(code: &str) -> String {
+    let mut fence = "```".to_string();
+    while code.contains(&fence) {
+        fence.push('`');
+    }
+    fence
+}
+
+/// Render the inline content of a block, such as a paragraph, that starts on a new line.
+fn markdown_block(inlines: &[WikiInline]) -> String {
+    escape_line_starts(&markdown(inlines))
+}
+
+/// Escape the characters at the start of each line that Markdown would read
+/// as the start of a heading, a list, or a quote, such as `1.` in `1. not a list`.
+fn escape_line_starts(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let content = line.trim_start_matches(' ');
+            let indent = &line[..line.len() - content.len()];
+            let digits = content.len()
+                - content
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .len();
+
+            if content.starts_with(['#', '+', '-', '=', '>']) {
+                format!("{indent}\\{content}")
+            } else if digits > 0 && content[digits..].starts_with(['.', ')']) {
+                format!("{indent}{}\\{}", &content[..digits], &content[digits..])
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Percent-encode the characters that would end a Markdown link destination early.
+fn markdown_url(url: &str) -> String {
+    let mut encoded = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '(' => encoded.push_str("%28"),
+            ')' => encoded.push_str("%29"),
+            '<' => encoded.push_str("%3C"),
+            '>' => encoded.push_str("%3E"),
+            '\n' => encoded.push_str("%0A"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Whether the URL is safe to use as a link or image in HTML: either a relative URL,
+/// or an absolute URL with the `http`, `https`, or `mailto` scheme.
+/// Other schemes, such as `javascript:`, could run code in the browser.
+fn is_safe_url(url: &str) -> bool {
+    match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => {
+            // Browsers ignore whitespace and control characters in the scheme.
+            let scheme: String = url[..end]
+                .chars()
+                .filter(|c| !c.is_whitespace() && !c.is_control())
+                .collect::<String>()
+                .to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Join the non-empty strings with the separator.
+fn join_nonempty(parts: impl Iterator<Item = String>, separator: &str) -> String {
+    parts
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/markup/description.jira");
+
+    #[test]
+    fn render_markdown() {
+        let document = WikiDocument::parse(FIXTURE);
+        assert_eq!(
+            document.to_markdown(),
+            include_str!("../tests/fixtures/markup/description.md").trim_end()
+        );
+    }
+
+    #[test]
+    fn render_html() {
+        let document = WikiDocument::parse(FIXTURE);
+        assert_eq!(
+            document.to_html(),
+            include_str!("../tests/fixtures/markup/description.html").trim_end()
+        );
+    }
+
+    #[test]
+    fn render_plain_text() {
+        let document = WikiDocument::parse(FIXTURE);
+        assert_eq!(
+            document.to_plain_text(),
+            include_str!("../tests/fixtures/markup/description.txt").trim_end()
+        );
+    }
+
+    #[test]
+    fn literal_delimiters() {
+        let document = WikiDocument::parse("A well-known 2 * 3 - 1 issue! Really!");
+        assert_eq!(
+            document.blocks,
+            [WikiBlock::Paragraph(vec![WikiInline::Text(
+                "A well-known 2 * 3 - 1 issue! Really!".to_string()
+            )])]
+        );
+    }
+
+    #[test]
+    fn nested_lists() {
+        let document = WikiDocument::parse("* one\n** one.a\n*# one.1\n* two\n# first");
+        assert_eq!(
+            document.to_markdown(),
+            "- one\n  - one.a\n  1. one.1\n- two\n\n1. first"
+        );
+    }
+
+    #[test]
+    fn unsafe_urls_render_as_text() {
+        let document = WikiDocument::parse("[click|javascript:alert(1)] !javascript:x! [a|/b]");
+        assert_eq!(
+            document.to_html(),
+            "<p>click javascript:x <a href=\"/b\">a</a></p>"
+        );
+    }
+
+    #[test]
+    fn markdown_url_with_parentheses() {
+        let document = WikiDocument::parse("[x|https://e.com/a b)c]");
+        assert_eq!(document.to_markdown(), "[x](https://e.com/a%20b%29c)");
+    }
+
+    #[test]
+    fn unsafe_urls_in_markdown() {
+        let document =
+            WikiDocument::parse("[click|javascript:alert(1)] [javascript://x] !javascript:x!");
+        assert_eq!(document.to_markdown(), "click javascript://x javascript:x");
+    }
+
+    #[test]
+    fn literal_line_markers() {
+        let document = WikiDocument::parse("1. not a list\\\\+ not a list either");
+        assert_eq!(
+            document.to_markdown(),
+            "1\\. not a list\\\n\\+ not a list either"
+        );
+    }
+}
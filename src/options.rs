@@ -0,0 +1,146 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/// Options that control which content Jira includes in the requested issues.
+///
+/// By default, Jira returns all navigable fields and expands nothing.
+/// If you select only some fields, deserialize the issues as `PartialIssue`,
+/// because `Issue` requires the complete set of fields.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    /// The fields to return, such as `summary` or `customfield_12345`.
+    /// Jira also accepts `*all`, `*navigable`, and field names prefixed with `-` to exclude them.
+    pub fields: Vec<String>,
+    /// The additional content to include in each issue.
+    pub expand: Vec<Expand>,
+    /// The issue properties to return, or `*all`.
+    pub properties: Vec<String>,
+}
+
+/// Additional content that Jira can include in an issue on request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expand {
+    /// The history of changes to the issue.
+    Changelog,
+    /// The fields rendered as HTML.
+    RenderedFields,
+    /// The display names of the fields.
+    Names,
+    /// The data types of the fields.
+    Schema,
+    /// The workflow transitions available from the current status.
+    Transitions,
+    /// The operations that the user can perform on the issue.
+    Operations,
+    /// The metadata for editing the fields.
+    EditMeta,
+    /// The fields in all available representations.
+    VersionedRepresentations,
+}
+
+impl Expand {
+    /// The identifier of this option in the `expand` query parameter.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Changelog => "changelog",
+            Self::RenderedFields => "renderedFields",
+            Self::Names => "names",
+            Self::Schema => "schema",
+            Self::Transitions => "transitions",
+            Self::Operations => "operations",
+            Self::EditMeta => "editmeta",
+            Self::VersionedRepresentations => "versionedRepresentations",
+        }
+    }
+}
+
+impl RequestOptions {
+    /// Options that request the default content.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            fields: Vec::new(),
+            expand: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Request only the specified fields.
+    #[must_use]
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields.extend(fields.iter().map(ToString::to_string));
+        self
+    }
+
+    /// Include the additional content in each issue.
+    #[must_use]
+    pub fn expand(mut self, expand: Expand) -> Self {
+        if !self.expand.contains(&expand) {
+            self.expand.push(expand);
+        }
+        self
+    }
+
+    /// Request the specified issue properties.
+    #[must_use]
+    pub fn properties(mut self, properties: &[&str]) -> Self {
+        self.properties
+            .extend(properties.iter().map(ToString::to_string));
+        self
+    }
+
+    /// The query parameters that these options add to the request URL.
+    pub(crate) fn query_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if !self.fields.is_empty() {
+            params.push(("fields", self.fields.join(",")));
+        }
+        if !self.expand.is_empty() {
+            let expand: Vec<&str> = self.expand.iter().map(|e| e.as_str()).collect();
+            params.push(("expand", expand.join(",")));
+        }
+        if !self.properties.is_empty() {
+            params.push(("properties", self.properties.join(",")));
+        }
+
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_params() {
+        let options = RequestOptions::new()
+            .fields(&["summary", "status"])
+            .expand(Expand::Changelog)
+            .expand(Expand::Names)
+            .expand(Expand::Changelog);
+
+        assert_eq!(
+            options.query_params(),
+            [
+                ("fields", "summary,status".to_string()),
+                ("expand", "changelog,names".to_string()),
+            ]
+        );
+        assert!(RequestOptions::default().query_params().is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+/// Controls how `JiraInstance` retries requests that failed for a transient reason.
+///
+/// Only idempotent GET requests are retried. Searches that fall back to POST
+/// because of a long query, and all requests that modify data, get a single attempt.
+/// A GET request is retried when Jira responds
+/// with one of the following statuses, or when the connection fails or times out:
+///
+/// * 429 Too Many Requests
+/// * 502 Bad Gateway
+/// * 503 Service Unavailable
+/// * 504 Gateway Timeout
+///
+/// The delay between attempts grows exponentially from `initial_backoff`
+/// up to `max_backoff`, with random jitter. If the response carries
+/// a `Retry-After` header, the delay follows the header instead,
+/// but it's still capped at `max_backoff`.
+///
+/// The default policy performs a single attempt and never retries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub initial_backoff: Duration,
+    /// The upper limit of the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Whether to follow the delay requested by Jira in the `Retry-After` header.
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries a failed request.
+    #[must_use]
+    pub const fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+            respect_retry_after: true,
+        }
+    }
+
+    /// A policy that retries a failed request until it reaches `max_attempts`,
+    /// with exponential backoff starting at 500 ms and limited to 60 s.
+    #[must_use]
+    pub const fn exponential(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::never()
+        }
+    }
+
+    /// Set the delay before the first retry.
+    #[must_use]
+    pub const fn initial_backoff(mut self, delay: Duration) -> Self {
+        self.initial_backoff = delay;
+        self
+    }
+
+    /// Set the upper limit of the delay between two attempts.
+    #[must_use]
+    pub const fn max_backoff(mut self, delay: Duration) -> Self {
+        self.max_backoff = delay;
+        self
+    }
+
+    /// Set whether to follow the delay requested by Jira in the `Retry-After` header.
+    #[must_use]
+    pub const fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    /// Decide whether to retry after the given attempt, which is numbered from 1.
+    /// Returns the delay to wait before the next attempt, or `None` if the result
+    /// is final and should go back to the caller.
+    pub(crate) fn next_delay(
+        &self,
+        attempt: u32,
+        result: &Result<reqwest::Response, reqwest::Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let retry_after = match result {
+            Ok(response) if is_transient(response.status()) => {
+                if self.respect_retry_after {
+                    retry_after(response.headers(), Utc::now())
+                } else {
+                    None
+                }
+            }
+            Err(error) if error.is_connect() || error.is_timeout() => None,
+            // The request either succeeded or failed in a way that another attempt won't fix.
+            _ => return None,
+        };
+
+        let delay = retry_after.unwrap_or_else(|| self.backoff(attempt));
+
+        Some(delay.min(self.max_backoff))
+    }
+
+    /// The exponential backoff delay after the given attempt, with full jitter.
+    /// The delay is a random duration between half of the exponential value and the full value.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let full = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        let half = full / 2;
+
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Whether the HTTP status signals a temporary condition that might go away on its own.
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Parse the `Retry-After` header, which is either a number of seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    // The HTTP date format is a subset of RFC 2822.
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means that the request can be retried immediately.
+    let delay = (date.with_timezone(&Utc) - now)
+        .to_std()
+        .unwrap_or(Duration::ZERO);
+
+    Some(delay)
+}
+
+/// A random number between 0 and 1.
+///
+/// The standard library seeds every `RandomState` randomly, which provides
+/// enough randomness for spreading out retries without an extra dependency.
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    // Keep the 53 bits that fit into the mantissa of an `f64`.
+    #[allow(clippy::cast_precision_loss)]
+    let fraction = (random >> 11) as f64 / (1_u64 << 53) as f64;
+    fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+
+        assert_eq!(
+            retry_after(&headers, Utc::now()),
+            Some(Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn retry_after_http_date() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:27:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn backoff_grows_and_stays_capped() {
+        let policy = RetryPolicy::exponential(10)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1));
+
+        for attempt in 1..10 {
+            let full = Duration::from_millis(100 * (1 << (attempt - 1))).min(policy.max_backoff);
+            let delay = policy.backoff(attempt);
+            assert!(delay >= full / 2 && delay <= full, "{delay:?} vs {full:?}");
+        }
+    }
+}
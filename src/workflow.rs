@@ -0,0 +1,136 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module models the workflow transitions that move an issue from one status to another.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::adf::RichText;
+use crate::edit::{self, IssueEdit};
+use crate::fields::FieldSchema;
+use crate::issue_model::Status;
+
+/// A transition that the workflow offers from the current status of an issue.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Transition {
+    pub id: String,
+    pub name: String,
+    /// The status that the issue moves to.
+    pub to: Status,
+    /// Whether the transition shows a screen that asks for field values.
+    #[serde(rename = "hasScreen")]
+    pub has_screen: Option<bool>,
+    /// The fields on the transition screen, by their IDs.
+    #[serde(default)]
+    pub fields: BTreeMap<String, TransitionField>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// A field on the screen of a transition.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TransitionField {
+    pub name: String,
+    /// Whether the transition requires a value of the field.
+    pub required: bool,
+    pub schema: Option<FieldSchema>,
+    /// The values that the field accepts, if it only accepts some.
+    #[serde(rename = "allowedValues")]
+    pub allowed_values: Option<Vec<Value>>,
+    /// The update operations that the field supports, such as `set`.
+    #[serde(default)]
+    pub operations: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The list of transitions that Jira returns for an issue.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Transitions {
+    pub transitions: Vec<Transition>,
+}
+
+/// A request to move an issue through a transition, with the changes
+/// that the transition screen collects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransitionRequest {
+    /// The ID or the name of the transition.
+    pub transition: String,
+    pub edit: IssueEdit,
+}
+
+impl TransitionRequest {
+    /// Request the transition with the ID or the name, such as `Close Issue`.
+    /// Names match regardless of case.
+    #[must_use]
+    pub fn new(transition: &str) -> Self {
+        Self {
+            transition: transition.to_string(),
+            edit: IssueEdit::new(),
+        }
+    }
+
+    /// Set the resolution, by its name, such as `Done`.
+    #[must_use]
+    pub fn resolution(mut self, name: &str) -> Self {
+        self.edit = self.edit.field("resolution", json!({ "name": name }));
+        self
+    }
+
+    /// Add a comment along with the transition.
+    #[must_use]
+    pub fn comment(mut self, body: impl Into<RichText>) -> Self {
+        let comment = json!({ "body": edit::rich_text(body.into()) });
+        self.edit = self.edit.add("comment", comment);
+        self
+    }
+
+    /// Set a field on the transition screen, such as `customfield_12345`, to the JSON value.
+    #[must_use]
+    pub fn field(mut self, id: &str, value: Value) -> Self {
+        self.edit = self.edit.field(id, value);
+        self
+    }
+
+    /// Apply the field changes along with the transition.
+    #[must_use]
+    pub fn edit(mut self, edit: IssueEdit) -> Self {
+        self.edit = self.edit.merge(edit);
+        self
+    }
+
+    /// Whether the request refers to the transition.
+    pub(crate) fn matches(&self, transition: &Transition) -> bool {
+        transition.id == self.transition || transition.name.eq_ignore_ascii_case(&self.transition)
+    }
+}
+
+/// The body of the request that performs a transition.
+#[derive(Serialize)]
+pub(crate) struct TransitionBody<'a> {
+    pub transition: TransitionId<'a>,
+    #[serde(flatten)]
+    pub edit: &'a IssueEdit,
+}
+
+/// A reference to a transition by its ID.
+#[derive(Serialize)]
+pub(crate) struct TransitionId<'a> {
+    pub id: &'a str,
+}
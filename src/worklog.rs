@@ -0,0 +1,203 @@
+/*
+Copyright 2022 Marek Suchánek <msuchane@redhat.com>
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// This module models the worklogs that record the time spent on issues,
+// and the requests that log, edit, or remove the time.
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::adf::RichText;
+use crate::edit;
+use crate::issue_model::{User, Visibility};
+
+/// A record of time that a user spent on an issue.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Worklog {
+    pub id: String,
+    #[serde(rename = "issueId")]
+    pub issue_id: String,
+    pub author: User,
+    #[serde(rename = "updateAuthor")]
+    pub update_author: User,
+    pub comment: Option<RichText>,
+    /// When the user started the work.
+    pub started: DateTime<Utc>,
+    /// The time spent in the Jira duration format, such as `3h 20m`.
+    #[serde(rename = "timeSpent")]
+    pub time_spent: String,
+    #[serde(rename = "timeSpentSeconds")]
+    pub time_spent_seconds: u64,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub visibility: Option<Visibility>,
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// The worklogs that changed in a period, for incremental synchronization.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdatedWorklogs {
+    /// The worklogs that users logged or edited in the period.
+    pub worklogs: Vec<Worklog>,
+    /// The end of the period. Pass it as the start of the next synchronization.
+    pub until: DateTime<Utc>,
+}
+
+/// How logging time changes the remaining estimate of the issue.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum EstimateAdjustment {
+    /// Reduce the estimate by the logged time, or restore it when removing a worklog.
+    #[default]
+    Auto,
+    /// Keep the estimate.
+    Leave,
+    /// Set the estimate to a new value in the Jira duration format, such as `2d`.
+    New(String),
+    /// Reduce the estimate by the value when logging time, or increase it by the value
+    /// when removing a worklog. Jira doesn't support this mode when editing a worklog,
+    /// so `JiraInstance::update_worklog` rejects it.
+    Manual(String),
+}
+
+impl EstimateAdjustment {
+    /// The query parameters that select the adjustment.
+    /// The `manual` parameter names the amount of the `Manual` mode, such as `reduceBy`.
+    pub(crate) fn params(&self, manual: &'static str) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Auto => vec![("adjustEstimate", "auto".to_string())],
+            Self::Leave => vec![("adjustEstimate", "leave".to_string())],
+            Self::New(estimate) => vec![
+                ("adjustEstimate", "new".to_string()),
+                ("newEstimate", estimate.clone()),
+            ],
+            Self::Manual(amount) => vec![
+                ("adjustEstimate", "manual".to_string()),
+                (manual, amount.clone()),
+            ],
+        }
+    }
+}
+
+/// The content of a new worklog, or the replacement of an existing worklog.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WorklogEntry {
+    #[serde(serialize_with = "jira_timestamp")]
+    started: DateTime<Utc>,
+    #[serde(rename = "timeSpentSeconds", serialize_with = "seconds")]
+    time_spent: Duration,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visibility: Option<Visibility>,
+}
+
+impl WorklogEntry {
+    /// Log the time spent on work that started at the specified moment.
+    /// Jira rounds the time down to whole minutes.
+    #[must_use]
+    pub fn new(started: DateTime<Utc>, time_spent: Duration) -> Self {
+        Self {
+            started,
+            time_spent,
+            comment: None,
+            visibility: None,
+        }
+    }
+
+    /// Describe the work.
+    #[must_use]
+    pub fn comment(mut self, comment: impl Into<RichText>) -> Self {
+        self.comment = Some(edit::rich_text(comment.into()));
+        self
+    }
+
+    /// Restrict who can see the worklog.
+    #[must_use]
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = Some(visibility);
+        self
+    }
+}
+
+/// Serialize the time in the format that Jira accepts, such as `2022-05-24T10:00:00.000+0000`.
+/// Jira rejects the RFC 3339 format that `chrono` uses by default.
+fn jira_timestamp<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&time.format("%Y-%m-%dT%H:%M:%S%.3f%z"))
+}
+
+/// Serialize the duration as whole seconds.
+fn seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+}
+
+/// A page of the list of worklogs that changed since a moment.
+#[derive(Debug, Deserialize)]
+pub(crate) struct WorklogChanges {
+    pub values: Vec<WorklogChange>,
+    /// The end of the period that the page covers, in milliseconds since the epoch.
+    pub until: i64,
+    #[serde(rename = "lastPage")]
+    pub last_page: bool,
+}
+
+/// A reference to a worklog that changed.
+#[derive(Debug, Deserialize)]
+pub(crate) struct WorklogChange {
+    #[serde(rename = "worklogId")]
+    pub worklog_id: u64,
+}
+
+/// The body of the request that downloads worklogs by their IDs.
+#[derive(Serialize)]
+pub(crate) struct WorklogIds<'a> {
+    pub ids: &'a [u64],
+}
+
+/// Convert the milliseconds since the epoch, which the worklog synchronization uses.
+pub(crate) fn from_millis(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn entry_format() {
+        let started = Utc.with_ymd_and_hms(2022, 5, 24, 10, 0, 0).unwrap();
+        let entry = WorklogEntry::new(started, Duration::from_secs(5400)).comment("Code review");
+
+        assert_eq!(
+            serde_json::to_value(&entry).unwrap(),
+            json!({
+                "started": "2022-05-24T10:00:00.000+0000",
+                "timeSpentSeconds": 5400,
+                "comment": "Code review",
+            })
+        );
+        assert_eq!(from_millis(1_653_386_400_000), started);
+    }
+}
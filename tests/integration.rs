@@ -1,3 +1,4 @@
+#[allow(clippy::single_component_path_imports)]
 use tokio;
 
 use jira_query::*;
@@ -53,8 +54,10 @@ async fn access_missing_issue() {
     let issues = instance.issues(&["CS-11111111111111111111"]).await;
 
     assert!(issues.is_err());
-    // TODO: This case should actually match JiraQueryError::NoIssues, not JiraQueryError::Rest. Fix it.
-    assert!(matches!(issues.unwrap_err(), JiraQueryError::Request(_)));
+    assert!(matches!(
+        issues.unwrap_err(),
+        JiraQueryError::MissingIssues(keys) if keys == ["CS-11111111111111111111"]
+    ));
 }
 
 /// Check that the issue fields contain the expected values.
@@ -0,0 +1,1997 @@
+//! Tests that run against a local stub server rather than a live Jira instance.
+//! They cover the behavior that public instances can't reproduce on demand,
+//! such as rate limiting or malformed responses.
+
+use std::time::Duration;
+
+use futures::{StreamExt, TryStreamExt};
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use jira_query::*;
+
+/// A complete issue in the JSON format that Jira sends, with the specified key.
+fn issue_json(key: &str) -> Value {
+    let mut issue: Value =
+        serde_json::from_str(include_str!("fixtures/issue.json")).expect("Invalid issue fixture.");
+    issue["key"] = json!(key);
+    issue
+}
+
+/// A response to a JQL search that contains issues with the specified keys.
+fn search_json(keys: &[&str]) -> Value {
+    page_json(keys, 0, keys.len())
+}
+
+/// A single page of a response to a JQL search, which contains issues with the specified keys.
+fn page_json(keys: &[&str], start_at: usize, total: usize) -> Value {
+    let issues: Vec<Value> = keys.iter().map(|key| issue_json(key)).collect();
+
+    json!({
+        "expand": "schema,names",
+        "startAt": start_at,
+        "maxResults": 50,
+        "total": total,
+        "issues": issues,
+    })
+}
+
+/// A `JiraInstance` that connects to the stub server.
+fn stub_jira(server: &MockServer) -> JiraInstance {
+    JiraInstance::at(server.uri()).unwrap()
+}
+
+/// A retry policy with negligible delays so that the tests finish quickly.
+fn fast_retry(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy::exponential(max_attempts)
+        .initial_backoff(Duration::from_millis(1))
+        .max_backoff(Duration::from_millis(10))
+}
+
+/// Check that rate-limited requests succeed after the server recovers.
+#[tokio::test]
+async fn retry_after_rate_limit() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue_json("TEST-1")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).retry(fast_retry(3));
+    let issue = instance.issue("TEST-1").await.unwrap();
+
+    assert_eq!(issue.key, "TEST-1");
+}
+
+/// Check that a single failed page doesn't discard the pages downloaded before it.
+#[tokio::test]
+async fn retry_page_during_pagination() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["TEST-1"])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server)
+        .paginate(Pagination::ChunkSize(2))
+        .retry(fast_retry(2));
+    let issues = instance.search("project = TEST").await.unwrap();
+
+    assert_eq!(issues.len(), 1);
+}
+
+/// Check that the instance gives up after the configured number of attempts.
+#[tokio::test]
+async fn retry_gives_up() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).retry(fast_retry(3));
+
+    assert!(instance.issue("TEST-1").await.is_err());
+}
+
+/// Check that errors that another attempt can't fix fail immediately.
+#[tokio::test]
+async fn no_retry_on_client_error() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(400))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).retry(fast_retry(3));
+
+    assert!(instance.issue("TEST-1").await.is_err());
+}
+
+/// Check that the default policy performs a single attempt.
+#[tokio::test]
+async fn no_retry_by_default() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(429))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    assert!(instance.issue("TEST-1").await.is_err());
+}
+
+/// Check that a search that falls back to POST isn't retried, because POST isn't idempotent.
+#[tokio::test]
+async fn no_retry_on_post() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).retry(fast_retry(3));
+    let query = format!("summary ~ \"{}\"", "x".repeat(3000));
+
+    assert!(instance.search(&query).await.is_err());
+}
+
+/// Check that a missing issue reports the messages from Jira.
+#[tokio::test]
+async fn error_not_found() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-404"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+            "errorMessages": ["Issue Does Not Exist"],
+            "errors": {},
+        })))
+        .mount(&server)
+        .await;
+
+    let error = stub_jira(&server).issue("TEST-404").await.unwrap_err();
+
+    assert!(
+        matches!(error, JiraQueryError::NotFound(messages) if messages == ["Issue Does Not Exist"])
+    );
+}
+
+/// Check that a rejected JQL query reports the messages from Jira.
+#[tokio::test]
+async fn error_invalid_jql() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "errorMessages": ["Field 'colour' does not exist or you do not have permission to view it."],
+            "errors": {},
+        })))
+        .mount(&server)
+        .await;
+
+    let error = stub_jira(&server).search("colour = red").await.unwrap_err();
+
+    assert!(matches!(error, JiraQueryError::InvalidJql(messages) if messages.len() == 1));
+}
+
+/// Check that rejected credentials result in a dedicated error.
+#[tokio::test]
+async fn error_unauthorized() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).authenticate(Auth::ApiKey("expired".to_string()));
+    let error = instance.issue("TEST-1").await.unwrap_err();
+
+    assert!(matches!(error, JiraQueryError::Unauthorized));
+}
+
+/// Check that an unexpected response body reports its beginning.
+#[tokio::test]
+async fn error_deserialize() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>Maintenance</html>"))
+        .mount(&server)
+        .await;
+
+    let error = stub_jira(&server).issue("TEST-1").await.unwrap_err();
+
+    assert!(matches!(
+        error,
+        JiraQueryError::Deserialize { body_snippet, .. } if body_snippet == "<html>Maintenance</html>"
+    ));
+}
+
+/// Check that `issues` reports the requested keys that Jira didn't return.
+#[tokio::test]
+async fn missing_issues() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["TEST-1"])))
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    let error = instance.issues(&["TEST-1", "TEST-2"]).await.unwrap_err();
+    assert!(matches!(error, JiraQueryError::MissingIssues(keys) if keys == ["TEST-2"]));
+
+    let found = instance
+        .issues_allow_missing(&["test-1", "TEST-2"])
+        .await
+        .unwrap();
+    assert_eq!(found.issues.len(), 1);
+    assert_eq!(found.missing, ["TEST-2"]);
+    assert!(found.moved.is_empty());
+}
+
+/// Check that an issue that moved to another key doesn't count as missing.
+#[tokio::test]
+async fn moved_issue() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["TEST-1", "OTHER-7"])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue_json("OTHER-7")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-3"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let found = stub_jira(&server)
+        .issues_allow_missing(&["TEST-1", "TEST-2", "TEST-3"])
+        .await
+        .unwrap();
+
+    assert_eq!(found.issues.len(), 2);
+    assert_eq!(found.missing, ["TEST-3"]);
+    assert_eq!(found.moved, [("TEST-2".to_string(), "OTHER-7".to_string())]);
+}
+
+/// Check that the stream requests pages lazily and stops at the last page.
+#[tokio::test]
+async fn stream_pages_lazily() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("startAt", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(
+            &["TEST-1", "TEST-2"],
+            0,
+            3,
+        )))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("startAt", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(&["TEST-3"], 2, 3)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).paginate(Pagination::ChunkSize(2));
+
+    // Reading only the first page must not request the second one.
+    let first_two: Vec<Issue> = instance
+        .search_stream("project = TEST")
+        .take(2)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(first_two.len(), 2);
+
+    let keys: Vec<String> = instance
+        .search_stream("project = TEST")
+        .map_ok(|issue| issue.key)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(keys, ["TEST-1", "TEST-2", "TEST-3"]);
+}
+
+/// Check that pagination continues when the instance caps the page size below the chunk size.
+#[tokio::test]
+async fn pagination_with_capped_page_size() {
+    let server = MockServer::start().await;
+
+    for (start_at, keys) in [("0", ["TEST-1", "TEST-2"]), ("2", ["TEST-3", "TEST-4"])] {
+        Mock::given(method("GET"))
+            .and(path("/rest/api/2/search"))
+            .and(query_param("maxResults", "100"))
+            .and(query_param("startAt", start_at))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page_json(
+                &keys,
+                start_at.parse().unwrap(),
+                5,
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("startAt", "4"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(&["TEST-5"], 4, 5)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).paginate(Pagination::ChunkSize(100));
+
+    let progress: Vec<(u32, Option<u32>)> = instance
+        .search_pages("project = TEST")
+        .map_ok(|page| (page.fetched(), page.total))
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(progress, [(2, Some(5)), (4, Some(5)), (5, Some(5))]);
+}
+
+/// Check that parallel pagination requests the remaining pages and keeps their order.
+#[tokio::test]
+async fn parallel_pagination() {
+    let server = MockServer::start().await;
+
+    let pages = [
+        ("0", vec!["TEST-1", "TEST-2"]),
+        ("2", vec!["TEST-3", "TEST-4"]),
+        ("4", vec!["TEST-5"]),
+    ];
+    for (index, (start_at, keys)) in pages.into_iter().enumerate() {
+        Mock::given(method("GET"))
+            .and(path("/rest/api/2/search"))
+            .and(query_param("startAt", start_at))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(page_json(&keys, start_at.parse().unwrap(), 5))
+                    // Let the later pages arrive sooner to check that the order holds.
+                    .set_delay(Duration::from_millis(50 / (index as u64 + 1))),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let instance = stub_jira(&server).paginate(Pagination::Parallel {
+        chunk_size: 2,
+        max_in_flight: 4,
+    });
+    let issues = instance.search("project = TEST").await.unwrap();
+    let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+
+    assert_eq!(keys, ["TEST-1", "TEST-2", "TEST-3", "TEST-4", "TEST-5"]);
+}
+
+/// Check that parallel pagination fails if any page fails.
+#[tokio::test]
+async fn parallel_pagination_failure() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("startAt", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(
+            &["TEST-1", "TEST-2"],
+            0,
+            6,
+        )))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("startAt", "2"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("startAt", "4"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(
+            &["TEST-5", "TEST-6"],
+            4,
+            6,
+        )))
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).paginate(Pagination::Parallel {
+        chunk_size: 2,
+        max_in_flight: 2,
+    });
+    let error = instance.search("project = TEST").await.unwrap_err();
+
+    assert!(matches!(error, JiraQueryError::Http { .. }));
+}
+
+/// Check that the request options reach Jira and that a reduced issue deserializes.
+#[tokio::test]
+async fn reduced_fields() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("fields", "summary,status"))
+        .and(query_param("expand", "names"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 0,
+            "maxResults": 50,
+            "total": 1,
+            "issues": [{
+                "id": "10001",
+                "key": "TEST-1",
+                "self": "https://jira.example.com/rest/api/2/issue/10001",
+                "fields": { "summary": "Login page does not load" },
+            }],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = RequestOptions::new()
+        .fields(&["summary", "status"])
+        .expand(Expand::Names);
+    let issues: Vec<PartialIssue> = stub_jira(&server)
+        .paginate(Pagination::ChunkSize(10))
+        .search_with("project = TEST", &options)
+        .await
+        .unwrap();
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].fields.summary.as_deref(),
+        Some("Login page does not load")
+    );
+    assert!(issues[0].fields.reporter.is_none());
+}
+
+/// Check that a single issue request carries no pagination parameters.
+#[tokio::test]
+async fn single_issue_ignores_pagination() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .and(query_param("expand", "changelog"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue_json("TEST-1")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).paginate(Pagination::ChunkSize(10));
+    let options = RequestOptions::new().expand(Expand::Changelog);
+    let issue: Issue = instance.issue_with("TEST-1", &options).await.unwrap();
+
+    assert_eq!(issue.key, "TEST-1");
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests[0].url.query(), Some("expand=changelog"));
+}
+
+/// A single change of the issue status.
+fn status_change_json(id: &str, created: &str, from: &str, to: &str) -> Value {
+    json!({
+        "id": id,
+        "created": created,
+        "items": [{
+            "field": "status",
+            "fieldtype": "jira",
+            "from": "1",
+            "fromString": from,
+            "to": "3",
+            "toString": to,
+        }],
+    })
+}
+
+/// Check that the changelog pages through the dedicated endpoint.
+#[tokio::test]
+async fn changelog_pages() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/changelog"))
+        .and(query_param("startAt", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 0,
+            "maxResults": 1,
+            "total": 2,
+            "isLast": false,
+            "values": [status_change_json("1", "2022-05-24T11:00:00.000+0000", "Open", "In Progress")],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/changelog"))
+        .and(query_param("startAt", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 1,
+            "maxResults": 1,
+            "total": 2,
+            "isLast": true,
+            "values": [status_change_json("2", "2022-05-25T11:00:00.000+0000", "In Progress", "Closed")],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let histories = stub_jira(&server).changelog("TEST-1").await.unwrap();
+    let statuses: Vec<Option<&str>> = histories
+        .iter()
+        .map(|history| history.items[0].to_string.as_deref())
+        .collect();
+
+    assert_eq!(statuses, [Some("In Progress"), Some("Closed")]);
+}
+
+/// Check that the changelog falls back to the embedded changelog on Jira Server.
+#[tokio::test]
+async fn changelog_embedded() {
+    let server = MockServer::start().await;
+
+    let mut issue = issue_json("TEST-1");
+    issue["changelog"] = json!({
+        "startAt": 0,
+        "maxResults": 1,
+        "total": 1,
+        "histories": [status_change_json("1", "2022-05-24T11:00:00.000+0000", "Open", "In Progress")],
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/changelog"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .and(query_param("expand", "changelog"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let histories = stub_jira(&server).changelog("TEST-1").await.unwrap();
+
+    assert_eq!(histories.len(), 1);
+    assert_eq!(histories[0].items[0].from_string.as_deref(), Some("Open"));
+}
+
+/// Check that special characters in JQL reach Jira intact.
+#[tokio::test]
+async fn encode_jql() {
+    let server = MockServer::start().await;
+    let query = r#"project = "R&D #1" AND summary ~ "C++" AND assignee = "Šárka""#;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("jql", query))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["TEST-1"])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let issues = stub_jira(&server).search(query).await.unwrap();
+
+    assert_eq!(issues.len(), 1);
+}
+
+/// Check that keys reach Jira encoded in the path and quoted in the JQL.
+#[tokio::test]
+async fn encode_keys() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1%2F..%3Fx%3D1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue_json("TEST-1")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("jql", r#"id in ("TEST-1","TEST-2) OR (x")"#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["TEST-1"])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    instance.issue("TEST-1/..?x=1").await.unwrap();
+    let found = instance
+        .issues_allow_missing(&["TEST-1", "TEST-2) OR (x"])
+        .await
+        .unwrap();
+    assert_eq!(found.missing, ["TEST-2) OR (x"]);
+}
+
+/// A responder to searches for keys that caps the page size like real Jira instances do.
+/// It answers each search with the issues that `lookup` finds for the keys
+/// in the `id in (...)` clause, starting at `startAt`, at most `page_size` at a time.
+fn capped_key_search(
+    page_size: usize,
+    lookup: impl Fn(&str) -> Option<Value> + Clone + Send + Sync + 'static,
+) -> impl Fn(&wiremock::Request) -> ResponseTemplate + Clone + Send + Sync + 'static {
+    move |request: &wiremock::Request| {
+        let (jql, start_at) = if request.method == wiremock::http::Method::POST {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            (
+                body["jql"].as_str().unwrap().to_string(),
+                body["startAt"].as_u64().unwrap_or_default() as usize,
+            )
+        } else {
+            let param = |name: &str| {
+                request
+                    .url
+                    .query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
+            (
+                param("jql").unwrap(),
+                param("startAt").map_or(0, |start_at| start_at.parse().unwrap()),
+            )
+        };
+        let found: Vec<Value> = jql
+            .trim_start_matches("id in (")
+            .trim_end_matches(')')
+            .split(',')
+            .map(|key| key.trim_matches('"'))
+            .filter_map(&lookup)
+            .collect();
+        let page: Vec<Value> = found
+            .iter()
+            .skip(start_at)
+            .take(page_size)
+            .cloned()
+            .collect();
+
+        ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": start_at,
+            "maxResults": page_size,
+            "total": found.len(),
+            "issues": page,
+        }))
+    }
+}
+
+/// Check that a long list of keys splits into several searches, each paging through the results.
+#[tokio::test]
+async fn search_many_keys() {
+    let server = MockServer::start().await;
+    let responder = capped_key_search(50, |key| Some(issue_json(key)));
+
+    // The first 250 keys exceed the URL length limit, so they use POST in 5 pages of 50.
+    // The remaining 50 keys fit in a GET search in a single page.
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(responder.clone())
+        .expect(5)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(responder)
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let keys: Vec<String> = (1..=300).map(|n| format!("TEST-{n}")).collect();
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let issues = stub_jira(&server).issues(&keys).await.unwrap();
+
+    let returned: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+    assert_eq!(returned, keys);
+}
+
+/// Check that a request for keys pages through all results with the default pagination,
+/// rather than report the keys past the first page as missing.
+#[tokio::test]
+async fn keys_beyond_page_cap() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(capped_key_search(50, |key| Some(issue_json(key))))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let keys: Vec<String> = (1..=60).map(|n| format!("T-{n}")).collect();
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let issues = stub_jira(&server).issues(&keys).await.unwrap();
+
+    assert_eq!(issues.len(), 60);
+}
+
+/// Check that a search on Jira Cloud follows the page tokens of the enhanced search.
+#[tokio::test]
+async fn cloud_token_pagination() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search/jql"))
+        .and(query_param("nextPageToken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issues": [issue_json("TEST-3")],
+            "isLast": true,
+        })))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search/jql"))
+        .and(query_param("jql", "project = TEST"))
+        .and(query_param("fields", "*navigable"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issues": [issue_json("TEST-1"), issue_json("TEST-2")],
+            "nextPageToken": "page-2",
+            "isLast": false,
+        })))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server)
+        .deployment(Deployment::Cloud)
+        .paginate(Pagination::ChunkSize(2));
+
+    let progress: Vec<u32> = instance
+        .search_pages("project = TEST")
+        .map_ok(|page| page.fetched())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(progress, [2, 3]);
+
+    let issues = instance.search("project = TEST").await.unwrap();
+    let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+    assert_eq!(keys, ["TEST-1", "TEST-2", "TEST-3"]);
+}
+
+/// Check that Jira Cloud downloads issues by key from the bulk fetch endpoint.
+#[tokio::test]
+async fn cloud_bulk_fetch() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue/bulkfetch"))
+        .and(body_partial_json(json!({
+            "issueIdsOrKeys": ["TEST-1", "TEST-2"],
+            "fields": ["*navigable"],
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issues": [issue_json("TEST-1")],
+            "issueErrors": [],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let found = stub_jira(&server)
+        .deployment(Deployment::Cloud)
+        .issues_allow_missing(&["TEST-1", "TEST-2"])
+        .await
+        .unwrap();
+
+    assert_eq!(found.issues.len(), 1);
+    assert_eq!(found.missing, ["TEST-2"]);
+}
+
+/// Check that API v3 reads rich text fields in the Atlassian Document Format.
+#[tokio::test]
+async fn api_v3_rich_text() {
+    let server = MockServer::start().await;
+
+    let mut issue = issue_json("TEST-1");
+    issue["fields"]["description"] = json!({
+        "version": 1,
+        "type": "doc",
+        "content": [{
+            "type": "paragraph",
+            "content": [
+                { "type": "text", "text": "Fails on " },
+                { "type": "text", "text": "save", "marks": [{ "type": "code" }] },
+            ],
+        }],
+    });
+    Mock::given(method("GET"))
+        .and(path("/rest/api/3/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let issue = stub_jira(&server)
+        .api_version(ApiVersion::V3)
+        .issue("TEST-1")
+        .await
+        .unwrap();
+    let description = issue.fields.description.unwrap();
+
+    assert!(matches!(description, RichText::Adf(_)));
+    assert_eq!(description.to_plain_text(), "Fails on save");
+}
+
+/// Check that the field registry downloads the fields once and resolves custom fields by name.
+#[tokio::test]
+async fn custom_field_by_name() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/field"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "id": "customfield_10016",
+                "name": "Story Points",
+                "custom": true,
+                "clauseNames": ["cf[10016]", "Story Points"],
+                "schema": { "type": "number", "customId": 10016 },
+            },
+            {
+                "id": "customfield_10020",
+                "name": "Target Release",
+                "custom": true,
+                "clauseNames": ["cf[10020]"],
+                "schema": { "type": "string", "customId": 10020 },
+            },
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut issue = issue_json("TEST-1");
+    issue["fields"]["customfield_10016"] = json!(5.0);
+    issue["fields"]["customfield_10020"] = Value::Null;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+    let issue = instance.issue("TEST-1").await.unwrap();
+    let registry = instance.field_registry().await.unwrap();
+
+    assert_eq!(
+        issue.custom_field_by_name(registry, "Story Points"),
+        Some(&json!(5.0))
+    );
+    assert_eq!(issue.custom_field_by_name(registry, "Target Release"), None);
+    assert_eq!(issue.custom_field_by_name(registry, "Sprint"), None);
+
+    // The second access reuses the downloaded fields.
+    let registry = instance.field_registry().await.unwrap();
+    assert_eq!(
+        registry.jql_name("Story Points").as_deref(),
+        Some("cf[10016]")
+    );
+}
+
+/// A page of values from the Agile API.
+fn agile_page_json(values: Value, start_at: usize, is_last: bool) -> Value {
+    json!({
+        "maxResults": 50,
+        "startAt": start_at,
+        "isLast": is_last,
+        "values": values,
+    })
+}
+
+/// Check that the Agile API lists boards across pages and sprints by state.
+#[tokio::test]
+async fn agile_boards_and_sprints() {
+    let server = MockServer::start().await;
+
+    let board = |id: u64| {
+        json!({
+            "id": id,
+            "name": format!("Board {id}"),
+            "type": "scrum",
+            "self": format!("{}/rest/agile/1.0/board/{id}", server.uri()),
+            "location": { "projectId": 10000, "projectKey": "TEST" },
+        })
+    };
+    Mock::given(method("GET"))
+        .and(path("/rest/agile/1.0/board"))
+        .and(query_param("startAt", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(agile_page_json(
+            json!([board(1)]),
+            0,
+            false,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/agile/1.0/board"))
+        .and(query_param("startAt", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(agile_page_json(
+            json!([board(2)]),
+            1,
+            true,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/agile/1.0/board/1/sprint"))
+        .and(query_param("state", "active,future"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(agile_page_json(
+            json!([{
+                "id": 7,
+                "name": "Sprint 7",
+                "state": "active",
+                "startDate": "2022-05-24T10:00:00.000+02:00",
+                "endDate": "2022-06-07T10:00:00.000+02:00",
+                "originBoardId": 1,
+                "goal": "Ship the export",
+            }]),
+            0,
+            true,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    let boards = instance.boards().await.unwrap();
+    let ids: Vec<u64> = boards.iter().map(|board| board.id).collect();
+    assert_eq!(ids, [1, 2]);
+
+    let sprints = instance
+        .sprints(1, &[SprintState::Active, SprintState::Future])
+        .await
+        .unwrap();
+    assert_eq!(sprints.len(), 1);
+    assert_eq!(sprints[0].state, SprintState::Active);
+    assert_eq!(sprints[0].goal.as_deref(), Some("Ship the export"));
+}
+
+/// Check that the issues in a sprint download in pages like a search.
+#[tokio::test]
+async fn agile_sprint_issues() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/agile/1.0/sprint/7/issue"))
+        .and(query_param("startAt", "0"))
+        .and(query_param("maxResults", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(
+            &["TEST-1", "TEST-2"],
+            0,
+            3,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/agile/1.0/sprint/7/issue"))
+        .and(query_param("startAt", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(&["TEST-3"], 2, 3)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let issues = stub_jira(&server)
+        .paginate(Pagination::ChunkSize(2))
+        .sprint_issues(7)
+        .await
+        .unwrap();
+
+    let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+    assert_eq!(keys, ["TEST-1", "TEST-2", "TEST-3"]);
+}
+
+/// Check that a sprint request with a long URL stays on the sprint endpoint,
+/// rather than fall back to a search without the scope of the sprint.
+#[tokio::test]
+async fn agile_long_url() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/agile/1.0/sprint/7/issue"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(&["TEST-1"], 0, 1)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/rest/api/2/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["OTHER-1"])))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let fields: Vec<String> = (10_000..10_150)
+        .map(|id| format!("customfield_{id}"))
+        .collect();
+    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+    let options = RequestOptions::new().fields(&fields);
+    let issues: Vec<PartialIssue> = stub_jira(&server)
+        .sprint_issues_with(7, &options)
+        .await
+        .unwrap();
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].key, "TEST-1");
+}
+
+/// Check that the sprints of an issue parse from both the legacy strings and JSON objects.
+#[tokio::test]
+async fn issue_sprints() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/field"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "id": "customfield_10020",
+            "name": "Sprint",
+            "custom": true,
+            "schema": {
+                "type": "array",
+                "items": "string",
+                "custom": "com.pyxis.greenhopper.jira:gh-sprint",
+                "customId": 10020,
+            },
+        }])))
+        .mount(&server)
+        .await;
+
+    let mut issue = issue_json("TEST-1");
+    issue["fields"]["customfield_10020"] = json!([
+        "com.atlassian.greenhopper.service.sprint.Sprint@1a2b[id=12,rapidViewId=3,state=CLOSED,name=Sprint 7,startDate=<null>,endDate=<null>,completeDate=<null>,sequence=12]",
+        { "id": 13, "name": "Sprint 8", "state": "active", "originBoardId": 3 },
+    ]);
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+    let issue = instance.issue("TEST-1").await.unwrap();
+    let sprints = issue
+        .sprints(instance.field_registry().await.unwrap())
+        .unwrap();
+
+    assert_eq!(
+        sprints.current.map(|sprint| sprint.name).as_deref(),
+        Some("Sprint 8")
+    );
+    let past: Vec<u64> = sprints.past.iter().map(|sprint| sprint.id).collect();
+    assert_eq!(past, [12]);
+}
+
+/// Check that creating an issue sends the fields once and returns the new key.
+#[tokio::test]
+async fn create_issue() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue"))
+        .and(body_partial_json(json!({
+            "fields": {
+                "project": { "key": "TEST" },
+                "issuetype": { "name": "Bug" },
+                "summary": "Export fails",
+            },
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "id": "10042",
+            "key": "TEST-42",
+            "self": format!("{}/rest/api/2/issue/10042", server.uri()),
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let issue = IssueEdit::new()
+        .project("TEST")
+        .issue_type("Bug")
+        .summary("Export fails")
+        .description("The export fails with *large* files.");
+    let created = stub_jira(&server).create_issue(&issue).await.unwrap();
+
+    assert_eq!(created.key, "TEST-42");
+}
+
+/// Check that Jira's validation errors reach the caller and that a write isn't retried.
+#[tokio::test]
+async fn create_issue_errors() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "errorMessages": [],
+            "errors": { "summary": "You must specify a summary of the issue." },
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).retry(fast_retry(3));
+
+    let error = instance
+        .create_issue(&IssueEdit::new().project("TEST").issue_type("Bug"))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        JiraQueryError::Http { errors, .. } if errors.contains_key("summary")
+    ));
+
+    let error = instance
+        .update_issue("TEST-1", &IssueEdit::new().add("labels", json!("flaky")))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, JiraQueryError::Http { status, .. } if status == 503));
+}
+
+fn transitions_json() -> Value {
+    json!({
+        "transitions": [
+            {
+                "id": "21",
+                "name": "Start Progress",
+                "to": {
+                    "self": "https://example.com/rest/api/2/status/3",
+                    "description": "",
+                    "iconUrl": "https://example.com/images/icons/statuses/inprogress.png",
+                    "name": "In Progress",
+                    "id": "3",
+                    "statusCategory": {
+                        "self": "https://example.com/rest/api/2/statuscategory/4",
+                        "id": 4,
+                        "key": "indeterminate",
+                        "colorName": "yellow",
+                        "name": "In Progress"
+                    }
+                },
+                "hasScreen": false,
+                "fields": {}
+            },
+            {
+                "id": "31",
+                "name": "Close Issue",
+                "to": {
+                    "self": "https://example.com/rest/api/2/status/6",
+                    "description": "",
+                    "iconUrl": "https://example.com/images/icons/statuses/closed.png",
+                    "name": "Closed",
+                    "id": "6",
+                    "statusCategory": {
+                        "self": "https://example.com/rest/api/2/statuscategory/3",
+                        "id": 3,
+                        "key": "done",
+                        "colorName": "green",
+                        "name": "Done"
+                    }
+                },
+                "hasScreen": true,
+                "fields": {
+                    "resolution": {
+                        "required": true,
+                        "name": "Resolution",
+                        "schema": { "type": "resolution", "system": "resolution" },
+                        "operations": ["set"],
+                        "allowedValues": [{ "id": "1", "name": "Done" }, { "id": "2", "name": "Won't Do" }]
+                    }
+                }
+            }
+        ]
+    })
+}
+
+#[tokio::test]
+async fn list_transitions() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/transitions"))
+        .and(query_param("expand", "transitions.fields"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(transitions_json()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let transitions = stub_jira(&server).transitions("TEST-1").await.unwrap();
+
+    assert_eq!(transitions.len(), 2);
+    assert_eq!(transitions[0].to.name, "In Progress");
+    let resolution = &transitions[1].fields["resolution"];
+    assert!(resolution.required);
+    assert_eq!(resolution.operations, ["set"]);
+    assert_eq!(resolution.allowed_values.as_ref().map(Vec::len), Some(2));
+}
+
+#[tokio::test]
+async fn transition_by_name() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/transitions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(transitions_json()))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue/TEST-1/transitions"))
+        .and(body_partial_json(json!({
+            "transition": { "id": "31" },
+            "fields": { "resolution": { "name": "Done" } },
+            "update": {
+                "comment": [{ "add": { "body": "Fixed in 1.2." } }],
+                "labels": [{ "add": "verified" }],
+            },
+        })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let request = TransitionRequest::new("close issue")
+        .resolution("Done")
+        .comment("Fixed in 1.2.")
+        .edit(IssueEdit::new().add("labels", json!("verified")));
+
+    stub_jira(&server)
+        .transition("TEST-1", &request)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn transition_unavailable() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/transitions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(transitions_json()))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let error = stub_jira(&server)
+        .transition("TEST-1", &TransitionRequest::new("Reopen Issue"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        &error,
+        JiraQueryError::TransitionUnavailable { available, .. } if available.len() == 2
+    ));
+    assert_eq!(
+        error.to_string(),
+        "The transition Reopen Issue isn't available for TEST-1. Available transitions: Start Progress, Close Issue"
+    );
+}
+
+/// A comment with the specified ID and body, by the author of the issue fixture.
+fn comment_json(id: &str, body: &str) -> Value {
+    let author = issue_json("TEST-1")["fields"]["creator"].clone();
+    json!({
+        "id": id,
+        "self": format!("https://jira.example.com/rest/api/2/issue/10000/comment/{id}"),
+        "author": author,
+        "updateAuthor": author,
+        "body": body,
+        "created": "2022-05-24T10:00:00.000+0000",
+        "updated": "2022-05-24T10:00:00.000+0000",
+    })
+}
+
+#[tokio::test]
+async fn comment_pages() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/comment"))
+        .and(query_param("startAt", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 0,
+            "maxResults": 2,
+            "total": 3,
+            "comments": [comment_json("1", "First"), comment_json("2", "Second")],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/comment"))
+        .and(query_param("startAt", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 2,
+            "maxResults": 2,
+            "total": 3,
+            "comments": [comment_json("3", "Third")],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let comments = stub_jira(&server).comments("TEST-1").await.unwrap();
+    let ids: Vec<&str> = comments.iter().map(|comment| comment.id.as_str()).collect();
+
+    assert_eq!(ids, ["1", "2", "3"]);
+    assert_eq!(comments[2].body.to_plain_text(), "Third");
+}
+
+#[tokio::test]
+async fn manage_comments() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue/TEST-1/comment"))
+        .and(body_partial_json(json!({
+            "body": "Reproduced on *Fedora*.",
+            "visibility": { "type": "role", "value": "Developers" },
+        })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(comment_json("10", "Reproduced on *Fedora*.")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/rest/api/2/issue/TEST-1/comment/10"))
+        .and(body_partial_json(
+            json!({ "body": "Reproduced on Fedora 36." }),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(comment_json("10", "Reproduced on Fedora 36.")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/rest/api/2/issue/TEST-1/comment/10"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    let comment = instance
+        .add_comment(
+            "TEST-1",
+            "Reproduced on *Fedora*.",
+            Some(&Visibility::role("Developers")),
+        )
+        .await
+        .unwrap();
+    assert_eq!(comment.id, "10");
+
+    let comment = instance
+        .update_comment("TEST-1", &comment.id, "Reproduced on Fedora 36.", None)
+        .await
+        .unwrap();
+    assert_eq!(comment.body.to_plain_text(), "Reproduced on Fedora 36.");
+
+    instance
+        .delete_comment("TEST-1", &comment.id)
+        .await
+        .unwrap();
+}
+
+/// A worklog with the specified ID and time spent, by the author of the issue fixture.
+fn worklog_json(id: &str, seconds: u64) -> Value {
+    let author = issue_json("TEST-1")["fields"]["creator"].clone();
+    json!({
+        "id": id,
+        "issueId": "10000",
+        "self": format!("https://jira.example.com/rest/api/2/issue/10000/worklog/{id}"),
+        "author": author,
+        "updateAuthor": author,
+        "comment": "Investigation",
+        "started": "2022-05-24T08:00:00.000+0000",
+        "timeSpent": "1h",
+        "timeSpentSeconds": seconds,
+        "created": "2022-05-24T10:00:00.000+0000",
+        "updated": "2022-05-24T10:00:00.000+0000",
+    })
+}
+
+#[tokio::test]
+async fn worklog_pages() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/worklog"))
+        .and(query_param("startAt", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 0,
+            "maxResults": 1,
+            "total": 2,
+            "worklogs": [worklog_json("1", 3600)],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/worklog"))
+        .and(query_param("startAt", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 1,
+            "maxResults": 1,
+            "total": 2,
+            "worklogs": [worklog_json("2", 1800)],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let worklogs = stub_jira(&server).worklogs("TEST-1").await.unwrap();
+    let total: u64 = worklogs.iter().map(|w| w.time_spent_seconds).sum();
+
+    assert_eq!(worklogs.len(), 2);
+    assert_eq!(total, 5400);
+    assert_eq!(worklogs[0].author.display_name, "Jane Doe");
+}
+
+#[tokio::test]
+async fn manage_worklogs() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue/TEST-1/worklog"))
+        .and(query_param("adjustEstimate", "manual"))
+        .and(query_param("reduceBy", "2h"))
+        .and(body_partial_json(json!({
+            "started": "2022-05-24T08:00:00.000+0000",
+            "timeSpentSeconds": 3600,
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(worklog_json("5", 3600)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/rest/api/2/issue/TEST-1/worklog/5"))
+        .and(query_param("adjustEstimate", "new"))
+        .and(query_param("newEstimate", "1d"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(worklog_json("5", 7200)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/rest/api/2/issue/TEST-1/worklog/5"))
+        .and(query_param("adjustEstimate", "leave"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+    let started = "2022-05-24T08:00:00Z".parse().unwrap();
+
+    let worklog = instance
+        .add_worklog(
+            "TEST-1",
+            &WorklogEntry::new(started, Duration::from_secs(3600)).comment("Investigation"),
+            &EstimateAdjustment::Manual("2h".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(worklog.id, "5");
+
+    let rejected = instance
+        .update_worklog(
+            "TEST-1",
+            &worklog.id,
+            &WorklogEntry::new(started, Duration::from_secs(7200)),
+            &EstimateAdjustment::Manual("1h".to_string()),
+        )
+        .await;
+    assert!(matches!(
+        rejected,
+        Err(JiraQueryError::UnsupportedAdjustment)
+    ));
+
+    let worklog = instance
+        .update_worklog(
+            "TEST-1",
+            &worklog.id,
+            &WorklogEntry::new(started, Duration::from_secs(7200)),
+            &EstimateAdjustment::New("1d".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(worklog.time_spent_seconds, 7200);
+
+    instance
+        .delete_worklog("TEST-1", &worklog.id, &EstimateAdjustment::Leave)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn sync_updated_worklogs() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/worklog/updated"))
+        .and(query_param("since", "1653386400000"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "values": [{ "worklogId": 1, "updatedTime": 1653386500000_i64, "properties": [] }],
+            "since": 1653386400000_i64,
+            "until": 1653386500000_i64,
+            "lastPage": false,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/worklog/updated"))
+        .and(query_param("since", "1653386500000"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "values": [{ "worklogId": 2, "updatedTime": 1653386600000_i64, "properties": [] }],
+            "since": 1653386500000_i64,
+            "until": 1653386600000_i64,
+            "lastPage": true,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/worklog/list"))
+        .and(body_partial_json(json!({ "ids": [1, 2] })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([worklog_json("1", 3600), worklog_json("2", 600)])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let since = "2022-05-24T10:00:00Z".parse().unwrap();
+    let updated = stub_jira(&server).updated_worklogs(since).await.unwrap();
+
+    assert_eq!(updated.worklogs.len(), 2);
+    assert_eq!(updated.until.timestamp_millis(), 1_653_386_600_000);
+}
+
+/// An attachment with the specified ID, served by the stub server.
+fn attachment_json(server: &MockServer, id: &str, filename: &str) -> Value {
+    json!({
+        "id": id,
+        "self": format!("{}/rest/api/2/attachment/{id}", server.uri()),
+        "filename": filename,
+        "author": issue_json("TEST-1")["fields"]["creator"].clone(),
+        "created": "2022-05-24T10:00:00.000+0000",
+        "size": 11,
+        "mimeType": "text/plain",
+        "content": format!("{}/secure/attachment/{id}/{filename}", server.uri()),
+    })
+}
+
+#[tokio::test]
+async fn download_attachment() {
+    let server = MockServer::start().await;
+
+    let mut issue = issue_json("TEST-1");
+    issue["fields"]["attachment"] = json!([attachment_json(&server, "10", "build.log")]);
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/secure/attachment/10/build.log"))
+        .and(header("Authorization", "Bearer secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Build fails"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server).authenticate(Auth::ApiKey("secret".to_string()));
+    let issue = instance.issue("TEST-1").await.unwrap();
+    let attachment = &issue.fields.attachment[0];
+    assert_eq!(attachment.mime_type, "text/plain");
+
+    let mut content = Vec::new();
+    let written = instance
+        .download_attachment(attachment, &mut content)
+        .await
+        .unwrap();
+
+    assert_eq!(written, 11);
+    assert_eq!(content, b"Build fails");
+}
+
+#[tokio::test]
+async fn upload_attachment() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue/TEST-1/attachments"))
+        .and(header("X-Atlassian-Token", "no-check"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!([attachment_json(
+                &server,
+                "11",
+                "build.log"
+            )])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let attachments = stub_jira(&server)
+        .upload_attachment("TEST-1", "build.log", b"Build fails".to_vec())
+        .await
+        .unwrap();
+
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].filename, "build.log");
+
+    let requests = server.received_requests().await.unwrap();
+    let body = String::from_utf8_lossy(&requests[0].body);
+    assert!(body.contains(r#"filename="build.log""#));
+    assert!(body.contains("Build fails"));
+}
+
+#[tokio::test]
+async fn manage_issue_links() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issueLinkType"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issueLinkTypes": [{
+                "id": "10000",
+                "name": "Blocks",
+                "inward": "is blocked by",
+                "outward": "blocks",
+                "self": "https://jira.example.com/rest/api/2/issueLinkType/10000",
+            }],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issueLink"))
+        .and(body_partial_json(json!({
+            "type": { "name": "Blocks" },
+            "inwardIssue": { "key": "TEST-1" },
+            "outwardIssue": { "key": "TEST-2" },
+            "comment": { "body": "Found during the release." },
+        })))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/rest/api/2/issueLink/10100"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    let link_types = instance.link_types().await.unwrap();
+    assert_eq!(link_types[0].outward, "blocks");
+
+    instance
+        .create_link(
+            "TEST-1",
+            "TEST-2",
+            "Blocks",
+            Some("Found during the release.".into()),
+        )
+        .await
+        .unwrap();
+    instance.delete_link("10100").await.unwrap();
+}
+
+#[tokio::test]
+async fn manage_remote_links() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/TEST-1/remotelink"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "id": 10000,
+            "self": "https://jira.example.com/rest/api/2/issue/TEST-1/remotelink/10000",
+            "globalId": "https://github.com/example/project/pull/42",
+            "application": { "type": "com.github", "name": "GitHub" },
+            "relationship": "fixed by",
+            "object": {
+                "url": "https://github.com/example/project/pull/42",
+                "title": "Fix the export",
+                "icon": { "url16x16": "https://github.com/favicon.ico", "title": "GitHub" },
+                "status": { "resolved": true, "icon": {} },
+            },
+        }])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/rest/api/2/issue/TEST-1/remotelink"))
+        .and(body_partial_json(json!({
+            "globalId": "https://ci.example.com/builds/7",
+            "application": { "type": "com.example.ci", "name": "CI" },
+            "object": {
+                "url": "https://ci.example.com/builds/7",
+                "title": "Build 7",
+                "status": { "resolved": false },
+            },
+        })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "id": 10001,
+            "self": "https://jira.example.com/rest/api/2/issue/TEST-1/remotelink/10001",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/rest/api/2/issue/TEST-1/remotelink/10001"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    let links = instance.remote_links("TEST-1").await.unwrap();
+    assert_eq!(links[0].object.title, "Fix the export");
+    assert_eq!(
+        links[0].object.status.as_ref().map(|s| s.resolved),
+        Some(true)
+    );
+
+    let build = RemoteLinkEdit::new("https://ci.example.com/builds/7", "Build 7")
+        .global_id("https://ci.example.com/builds/7")
+        .application("com.example.ci", "CI")
+        .resolved(false);
+    let created = instance
+        .create_or_update_remote_link("TEST-1", &build)
+        .await
+        .unwrap();
+    assert_eq!(created.id, 10001);
+
+    instance
+        .delete_remote_link("TEST-1", created.id)
+        .await
+        .unwrap();
+}
+
+/// A reference to an issue, as it appears in the links and subtasks of another issue.
+fn condensed_json(key: &str) -> Value {
+    let issue = issue_json(key);
+    json!({
+        "id": issue["id"],
+        "key": key,
+        "self": issue["self"],
+        "fields": {
+            "issuetype": issue["fields"]["issuetype"],
+            "status": issue["fields"]["status"],
+            "summary": issue["fields"]["summary"],
+        },
+    })
+}
+
+/// A link of the `Blocks` type, in the direction of the linked issue.
+fn blocks_json(direction: &str, key: &str) -> Value {
+    json!({
+        "id": "10100",
+        "self": "https://jira.example.com/rest/api/2/issueLink/10100",
+        "type": {
+            "id": "10000",
+            "name": "Blocks",
+            "inward": "is blocked by",
+            "outward": "blocks",
+            "self": "https://jira.example.com/rest/api/2/issueLinkType/10000",
+        },
+        direction: condensed_json(key),
+    })
+}
+
+#[tokio::test]
+async fn walk_issue_graph() {
+    let server = MockServer::start().await;
+
+    // TEST-4 blocks TEST-1, which blocks TEST-2, which has the TEST-3 subtask.
+    // TEST-5 is a subtask of TEST-2, but the account can't see it.
+    let mut issues = std::collections::HashMap::new();
+    let mut issue = issue_json("TEST-1");
+    issue["fields"]["issuelinks"] = json!([
+        blocks_json("outwardIssue", "TEST-2"),
+        blocks_json("inwardIssue", "TEST-4"),
+    ]);
+    issues.insert("TEST-1", issue);
+    let mut issue = issue_json("TEST-2");
+    issue["fields"]["issuelinks"] = json!([blocks_json("inwardIssue", "TEST-1")]);
+    issue["fields"]["subtasks"] = json!([condensed_json("TEST-3"), condensed_json("TEST-5")]);
+    issues.insert("TEST-2", issue);
+    issues.insert("TEST-3", issue_json("TEST-3"));
+    issues.insert("TEST-4", issue_json("TEST-4"));
+
+    let responder = move |request: &wiremock::Request| {
+        let (_, jql) = request
+            .url
+            .query_pairs()
+            .find(|(name, _)| name == "jql")
+            .unwrap();
+        let found: Vec<Value> = jql
+            .trim_start_matches("id in (")
+            .trim_end_matches(')')
+            .split(',')
+            .filter_map(|key| issues.get(key.trim_matches('"')).cloned())
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({
+            "startAt": 0,
+            "maxResults": 50,
+            "total": found.len(),
+            "issues": found,
+        }))
+    };
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/field"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(responder)
+        .expect(5)
+        .mount(&server)
+        .await;
+
+    let instance = stub_jira(&server);
+
+    let graph = instance
+        .issue_graph(&["TEST-1"], &GraphOptions::new().depth(2))
+        .await
+        .unwrap();
+    assert_eq!(graph.len(), 4);
+    assert_eq!(graph.missing(), ["TEST-5"]);
+    assert_eq!(graph.edges_from("TEST-2").count(), 1);
+    assert_eq!(
+        graph.topological_order().unwrap(),
+        ["TEST-4", "TEST-1", "TEST-2", "TEST-3"]
+    );
+    assert!(graph
+        .to_dot()
+        .contains(r#""TEST-1" -> "TEST-2" [label="blocks"];"#));
+
+    // Following only the outward links from TEST-1 skips TEST-4 and the subtasks.
+    let options = GraphOptions::new()
+        .depth(2)
+        .link_type("blocks", LinkDirection::Outward)
+        .subtasks(false);
+    let graph = instance.issue_graph(&["TEST-1"], &options).await.unwrap();
+    let keys: Vec<&str> = graph.issues().map(|issue| issue.key.as_str()).collect();
+    assert_eq!(keys, ["TEST-1", "TEST-2"]);
+}
+
+/// Check that the graph downloads all issues of a frontier that spans several pages.
+#[tokio::test]
+async fn issue_graph_large_frontier() {
+    let server = MockServer::start().await;
+
+    // TEST-1 has 60 subtasks, but Jira returns at most 50 issues in a page.
+    let subtasks: Vec<String> = (2..=61).map(|n| format!("TEST-{n}")).collect();
+    let mut root = issue_json("TEST-1");
+    root["fields"]["subtasks"] = subtasks
+        .iter()
+        .map(|key| condensed_json(key))
+        .collect::<Vec<Value>>()
+        .into();
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/field"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .respond_with(capped_key_search(50, move |key| {
+            Some(if key == "TEST-1" {
+                root.clone()
+            } else {
+                issue_json(key)
+            })
+        }))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let graph = stub_jira(&server)
+        .issue_graph(&["TEST-1"], &GraphOptions::new())
+        .await
+        .unwrap();
+
+    assert_eq!(graph.len(), 61);
+    assert!(graph.missing().is_empty());
+    assert_eq!(graph.edges_from("TEST-1").count(), 60);
+}
+
+/// Check that a query from the JQL builder reaches Jira with the correct quoting.
+#[tokio::test]
+async fn search_jql() {
+    let server = MockServer::start().await;
+    let expected =
+        r#"project = "R&D #1" AND status NOT IN ("Closed", "Done") ORDER BY updated DESC"#;
+
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/search"))
+        .and(query_param("jql", expected))
+        .respond_with(ResponseTemplate::new(200).set_body_json(search_json(&["TEST-1"])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let query = Jql::field("project")
+        .eq("R&D #1")
+        .and(Jql::field("status").not_in(["Closed", "Done"]))
+        .order_by("updated", SortOrder::Desc);
+    let issues = stub_jira(&server).search_jql(&query).await.unwrap();
+
+    assert_eq!(issues.len(), 1);
+}